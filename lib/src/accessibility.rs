@@ -0,0 +1,175 @@
+use crate::color::{LAB, RGB};
+use std::str::FromStr;
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Number of steps while searching for the lightness of a text color
+const SEARCH_STEPS: u8 = 20;
+
+/// WCAG 2.x conformance levels for the contrast of text over its background
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum WCAGLevel {
+    /// Contrast ratio of 3 for large text
+    AALarge,
+    /// Contrast ratio of 4.5
+    AA,
+    /// Contrast ratio of 7
+    AAA,
+}
+
+impl FromStr for WCAGLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "aa-large" => Ok(WCAGLevel::AALarge),
+            "aa" => Ok(WCAGLevel::AA),
+            "aaa" => Ok(WCAGLevel::AAA),
+            _ => Err(format!("Unknown WCAG level '{}'", s)),
+        }
+    }
+}
+
+impl WCAGLevel {
+    /**
+     * Minimum contrast ratio of the level
+     */
+    pub fn ratio(&self) -> f32 {
+        match self {
+            WCAGLevel::AALarge => 3.0,
+            WCAGLevel::AA => 4.5,
+            WCAGLevel::AAA => 7.0,
+        }
+    }
+}
+
+/// Text color suggested for a background
+pub struct TextColor {
+    pub color: RGB,
+    /// WCAG 2.x contrast ratio of the text color over the background
+    pub contrast: f32,
+}
+
+impl TextColor {
+    /**
+     * Checks if the contrast of the text color meets the level
+     */
+    pub fn meets(&self, level: WCAGLevel) -> bool {
+        return self.contrast >= level.ratio();
+    }
+}
+
+// Finds the lightness closest to the color's own lightness which meets the target contrast over the background,
+// by moving it towards black or white. Returns the adjusted color and the change in lightness.
+fn adjust_lightness(color: &LAB, background: &RGB, target: f32, darken: bool) -> Option<(RGB, f32)> {
+    let with_lightness = |l: f32| RGB::from(&LAB { l, a: color.a, b: color.b });
+    let meets = |rgb: &RGB| rgb.contrast_ratio(background) >= target;
+
+    let original = with_lightness(color.l);
+    if meets(&original) {
+        return Some((original, 0.0));
+    }
+
+    let extreme = if darken { 0.0 } else { 100.0 };
+    if !meets(&with_lightness(extreme)) {
+        return None;
+    }
+
+    // Lightness between `near` (fails) and `far` (meets) is narrowed down
+    let (mut near, mut far) = (color.l, extreme);
+    for _ in 0..SEARCH_STEPS {
+        let mid = (near + far) / 2.0;
+        if meets(&with_lightness(mid)) {
+            far = mid;
+        } else {
+            near = mid;
+        }
+    }
+
+    return Some((with_lightness(far), (far - color.l).abs()));
+}
+
+/// Suggests a text color for the background which meets the target WCAG 2.x contrast ratio
+///
+/// Every candidate color is made darker (over light backgrounds) or lighter (over dark backgrounds) in LAB
+/// until it meets the target, and the candidate which changes the least is picked, keeping the tint of
+/// the candidate. When no candidate can meet the target, black or white is picked, whichever has a higher contrast.
+pub fn text_color(background: &LAB, candidates: &[LAB], target: f32) -> TextColor {
+    let bg = RGB::from(background);
+    let black = RGB { r: 0, g: 0, b: 0 };
+    let white = RGB { r: 255, g: 255, b: 255 };
+    let darken = black.contrast_ratio(&bg) >= white.contrast_ratio(&bg);
+
+    let best = candidates
+        .iter()
+        .filter_map(|color| adjust_lightness(color, &bg, target, darken))
+        .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("NaN encountered"));
+
+    let color = match best {
+        Some((color, _)) => color,
+        None => if darken { black } else { white },
+    };
+
+    return TextColor {
+        contrast: color.contrast_ratio(&bg),
+        color,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lab(r: u8, g: u8, b: u8) -> LAB {
+        return LAB::from(&RGB { r, g, b });
+    }
+
+    #[test]
+    fn text_color_meets_every_level() {
+        let backgrounds = [lab(255, 255, 255), lab(20, 30, 60), lab(230, 60, 50), lab(120, 120, 120), lab(250, 220, 90)];
+        let candidates = [lab(30, 90, 200), lab(240, 180, 40), lab(20, 140, 60)];
+
+        for level in [WCAGLevel::AALarge, WCAGLevel::AA, WCAGLevel::AAA].iter() {
+            for background in backgrounds.iter() {
+                // Level can only be met when black or white meets it
+                let bg = RGB::from(background);
+                let best = RGB { r: 0, g: 0, b: 0 }.contrast_ratio(&bg).max(RGB { r: 255, g: 255, b: 255 }.contrast_ratio(&bg));
+                let text = text_color(background, &candidates, level.ratio());
+                if best >= level.ratio() {
+                    assert!(text.meets(*level), "{} over {} does not meet {}", text.color.hex(), bg.hex(), level.ratio());
+                } else {
+                    assert!(text.contrast == best);
+                }
+                assert!((text.contrast - text.color.contrast_ratio(&RGB::from(background))).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn text_color_keeps_tint_of_candidates() {
+        // Dark blue already meets AA over white, so it is kept
+        let text = text_color(&lab(255, 255, 255), &[lab(20, 40, 160)], WCAGLevel::AA.ratio());
+        assert_eq!(text.color.hex(), "#1428A0");
+
+        // Light blue is darkened while staying blue
+        let text = text_color(&lab(255, 255, 255), &[lab(120, 160, 255)], WCAGLevel::AA.ratio());
+        let rgb = text.color;
+        assert!(rgb.b > rgb.r && rgb.b > rgb.g);
+        assert!(text.contrast >= 4.5 && text.contrast < 5.0);
+    }
+
+    #[test]
+    fn text_color_falls_back_to_black_or_white() {
+        let text = text_color(&lab(255, 255, 255), &[], WCAGLevel::AA.ratio());
+        assert_eq!(text.color.hex(), "#000000");
+        let text = text_color(&lab(10, 10, 40), &[], WCAGLevel::AA.ratio());
+        assert_eq!(text.color.hex(), "#FFFFFF");
+
+        // Contrast of 21 is impossible over gray
+        let text = text_color(&lab(120, 120, 120), &[lab(30, 90, 200)], 21.0);
+        assert_eq!(text.color.hex(), "#000000");
+        assert!(!text.meets(WCAGLevel::AAA));
+    }
+}
@@ -0,0 +1,210 @@
+use crate::{color::LAB, Pixels};
+use std::collections::VecDeque;
+
+/// Maximum number of border pixels tried as the reference background color
+const MAX_CANDIDATES: usize = 256;
+
+/// Background detected from the borders of an image
+pub struct Background {
+    /// Average color of the background pixels
+    pub color: LAB,
+    /// Fraction of the considered pixels that belong to the background
+    pub coverage: f32,
+    /// Whether each pixel of the grid belongs to the background
+    pub mask: Vec<bool>,
+}
+
+impl Background {
+    /**
+     * Collects the pixels of the grid that are not part of the background
+     */
+    pub fn exclude(&self, grid: &[Option<LAB>]) -> Pixels {
+        return grid
+            .iter()
+            .zip(self.mask.iter())
+            .filter_map(|(pixel, &is_bg)| if is_bg { None } else { pixel.clone() })
+            .collect();
+    }
+}
+
+/// Returns the average of a group of colors
+fn mean(colors: &[&LAB]) -> LAB {
+    let mut avg = LAB { l: 0.0, a: 0.0, b: 0.0 };
+    let len = colors.len() as f32;
+
+    for color in colors.iter() {
+        avg.l += color.l / len;
+        avg.a += color.a / len;
+        avg.b += color.b / len;
+    }
+
+    return avg;
+}
+
+/// Detects a uniform background by flood filling inwards from the borders of the image
+///
+/// `grid` holds `width * height` pixels in row-major order where `None` marks pixels that are ignored
+/// (like transparent ones). The most common border color is used as the reference, and every pixel
+/// connected to the border within `tolerance` (Delta E) of it is considered part of the background.
+/// `None` is returned when the border does not have a common color.
+pub fn detect_background(grid: &[Option<LAB>], width: usize, height: usize, tolerance: f32) -> Option<Background> {
+    if width == 0 || height == 0 || grid.len() != width * height {
+        return None;
+    }
+
+    // Indices of all the pixels on the border of the image
+    let mut border: Vec<usize> = Vec::new();
+    for x in 0..width {
+        border.push(x);
+        if height > 1 {
+            border.push((height - 1) * width + x);
+        }
+    }
+    for y in 1..height.saturating_sub(1) {
+        border.push(y * width);
+        if width > 1 {
+            border.push(y * width + width - 1);
+        }
+    }
+
+    let border_colors: Vec<&LAB> = border.iter().filter_map(|&i| grid[i].as_ref()).collect();
+    if border_colors.is_empty() {
+        return None;
+    }
+
+    // Reference color is the border color which has the most border colors similar to it
+    let step = (border_colors.len() / MAX_CANDIDATES).max(1);
+    let (support, candidate) = border_colors
+        .iter()
+        .step_by(step)
+        .map(|candidate| {
+            let support = border_colors
+                .iter()
+                .filter(|color| candidate.distance(color) <= tolerance)
+                .count();
+            (support, *candidate)
+        })
+        .max_by_key(|(support, _)| *support)
+        .unwrap();
+
+    // At least half of the border should have the background color
+    if support * 2 < border_colors.len() {
+        return None;
+    }
+
+    let similar: Vec<&LAB> = border_colors
+        .iter()
+        .cloned()
+        .filter(|color| candidate.distance(color) <= tolerance)
+        .collect();
+    let reference = mean(&similar);
+    let is_background = |i: usize| match &grid[i] {
+        Some(color) => reference.distance(color) <= tolerance,
+        None => false,
+    };
+
+    // Breadth first flood fill starting from the matching border pixels
+    let mut mask = vec![false; grid.len()];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for &i in border.iter() {
+        if !mask[i] && is_background(i) {
+            mask[i] = true;
+            queue.push_back(i);
+        }
+    }
+
+    while let Some(i) = queue.pop_front() {
+        let (x, y) = (i % width, i / width);
+        let mut neighbours = Vec::with_capacity(4);
+        if x > 0 { neighbours.push(i - 1); }
+        if x + 1 < width { neighbours.push(i + 1); }
+        if y > 0 { neighbours.push(i - width); }
+        if y + 1 < height { neighbours.push(i + width); }
+
+        for n in neighbours {
+            if !mask[n] && is_background(n) {
+                mask[n] = true;
+                queue.push_back(n);
+            }
+        }
+    }
+
+    let bg_colors: Vec<&LAB> = grid
+        .iter()
+        .zip(mask.iter())
+        .filter_map(|(pixel, &is_bg)| if is_bg { pixel.as_ref() } else { None })
+        .collect();
+    let total = grid.iter().filter(|pixel| pixel.is_some()).count();
+
+    return Some(Background {
+        color: mean(&bg_colors),
+        coverage: bg_colors.len() as f32 / total as f32,
+        mask,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: LAB = LAB { l: 100.0, a: 0.0, b: 0.0 };
+    const RED: LAB = LAB { l: 54.0, a: 80.0, b: 67.0 };
+    const BLUE: LAB = LAB { l: 32.0, a: 79.0, b: -108.0 };
+
+    // 10x10 grid with a red ring from 2 to 8 around a white center from 4 to 6
+    fn ring(background: impl Fn(usize) -> LAB) -> Vec<Option<LAB>> {
+        return (0..100)
+            .map(|i| {
+                let (x, y) = (i % 10, i / 10);
+                let inside = |from, to| (from..to).contains(&x) && (from..to).contains(&y);
+                if inside(4, 6) {
+                    Some(WHITE)
+                } else if inside(2, 8) {
+                    Some(RED)
+                } else {
+                    Some(background(i))
+                }
+            })
+            .collect();
+    }
+
+    #[test]
+    fn detects_solid_border() {
+        let grid = ring(|_| WHITE);
+        let bg = detect_background(&grid, 10, 10, 10.0).unwrap();
+
+        assert!(bg.color.distance(&WHITE) < 0.01);
+        assert_eq!(bg.coverage, 0.64);
+        // White center is not connected to the border
+        assert!(!bg.mask[44] && !bg.mask[55]);
+        assert!(bg.mask[0] && bg.mask[99]);
+        assert_eq!(bg.exclude(&grid).len(), 36);
+    }
+
+    #[test]
+    fn detects_noisy_border() {
+        // Off-white background with a few blue pixels on the border
+        let grid = ring(|i| match i {
+            3 | 30 | 97 => BLUE,
+            _ => LAB { l: 96.0 + (i * 37 % 5) as f32, a: (i % 3) as f32 - 1.0, b: (i % 4) as f32 - 1.5 },
+        });
+        let bg = detect_background(&grid, 10, 10, 10.0).unwrap();
+
+        assert!(bg.color.distance(&WHITE) < 5.0);
+        assert!(!bg.mask[3] && !bg.mask[30] && !bg.mask[97]);
+        assert_eq!(bg.coverage, 0.61);
+
+        // Noise is not within a smaller tolerance, so the border has no common color
+        assert!(detect_background(&grid, 10, 10, 1.0).is_none());
+    }
+
+    #[test]
+    fn no_background_without_common_border_color() {
+        let colors = [WHITE, RED, BLUE];
+        let grid: Vec<Option<LAB>> = (0..100).map(|i| Some(colors[(i / 3) % 3].clone())).collect();
+        assert!(detect_background(&grid, 10, 10, 10.0).is_none());
+
+        assert!(detect_background(&vec![None; 100], 10, 10, 10.0).is_none());
+        assert!(detect_background(&ring(|_| WHITE), 10, 9, 10.0).is_none());
+    }
+}
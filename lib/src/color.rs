@@ -1,41 +1,497 @@
-use std::{convert::From, fmt};
-use serde_derive::Serialize;
+use std::{convert::From, fmt, str::FromStr};
+use crate::{format::{Format, ToCSS}, gamut::map_to_gamut, parse::parse_hex};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct RGB {
     pub r: u8,
     pub g: u8,
     pub b: u8
 }
 
+/// RGB color with an alpha channel
+///
+/// The color channels are straight (not premultiplied by alpha) unless created with `premultiply`
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct RGBA {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct LAB {
     pub l: f32,
     pub a: f32,
     pub b: f32
 }
 
-#[derive(Serialize, Clone)]
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct HSL {
     pub h: f32,
     pub s: f32,
     pub l: f32
 }
 
+/// Color in the polar form of LAB color space where hue is in degrees
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct LCH {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32
+}
+
+/// Color in the HSV (or HSB) color space normalized to 0 - 1
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HSV {
+    pub h: f32,
+    pub s: f32,
+    pub v: f32
+}
+
+/// Color in the HWB color space normalized to 0 - 1
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HWB {
+    pub h: f32,
+    pub w: f32,
+    pub b: f32
+}
+
+/// Color in the CMYK color space normalized to 0 - 1
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct CMYK {
+    pub c: f32,
+    pub m: f32,
+    pub y: f32,
+    pub k: f32
+}
+
+/// Ways of generating black ink while converting colors to CMYK
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum BlackGeneration {
+    /// Gray component of every color is fully replaced by black ink
+    Naive,
+    /// Gray component Replacement of the given fraction (0 - 1) of the gray component in every color
+    GCR(f32),
+    /// Under Color Removal of the given fraction (0 - 1) of the gray component only in neutral colors
+    UCR(f32),
+}
+
+/// Color in the OKLab color space where lightness is from 0 to 1
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OKLAB {
+    pub l: f32,
+    pub a: f32,
+    pub b: f32
+}
+
+/// Color in the polar form of OKLab color space where hue is in degrees
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct OKLCH {
+    pub l: f32,
+    pub c: f32,
+    pub h: f32
+}
+
+/// RGB working spaces with their transfer functions and primaries (relative to D65 white)
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum RGBSpace {
+    SRGB,
+    AdobeRGB,
+    DisplayP3,
+    Rec2020,
+}
+
+impl Default for RGBSpace {
+    fn default() -> Self {
+        RGBSpace::SRGB
+    }
+}
+
+/// Reference white points used by LAB, with their XYZ values (Y = 100)
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WhitePoint {
+    D50,
+    D65,
+    Custom(f32, f32, f32),
+}
+
+impl Default for WhitePoint {
+    fn default() -> Self {
+        WhitePoint::D65
+    }
+}
+
+impl FromStr for WhitePoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "d50" => Ok(WhitePoint::D50),
+            "d65" => Ok(WhitePoint::D65),
+            _ => Err(format!("Unknown white point '{}'", s)),
+        }
+    }
+}
+
+/// Methods of calculating the contrast of text over a background
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Contrast {
+    /// WCAG 2.x contrast ratio from 1 to 21, which is symmetric
+    WCAG,
+    /// APCA lightness contrast (Lc) from around -108 to 106, negative for light text on dark backgrounds
+    APCA,
+}
+
+impl FromStr for Contrast {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "wcag" => Ok(Contrast::WCAG),
+            "apca" => Ok(Contrast::APCA),
+            _ => Err(format!("Unknown contrast method '{}'", s)),
+        }
+    }
+}
+
+impl Contrast {
+    /**
+     * Calculates the contrast of the text color over the background color
+     */
+    pub fn calculate(&self, text: &RGB, background: &RGB) -> f32 {
+        match self {
+            Contrast::WCAG => text.contrast_ratio(background),
+            Contrast::APCA => text.apca_contrast(background),
+        }
+    }
+}
+
+/// Color vision deficiencies which can be simulated
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Deficiency {
+    /// Missing long-wavelength (red) cones
+    Protanopia,
+    /// Missing medium-wavelength (green) cones
+    Deuteranopia,
+    /// Missing short-wavelength (blue) cones
+    Tritanopia,
+    /// Complete lack of color vision, only the luminance is seen
+    Achromatopsia,
+}
+
+impl Deficiency {
+    /// All the deficiencies which can be simulated
+    pub const ALL: [Deficiency; 4] = [
+        Deficiency::Protanopia,
+        Deficiency::Deuteranopia,
+        Deficiency::Tritanopia,
+        Deficiency::Achromatopsia,
+    ];
+
+    /**
+     * Simulates how linear sRGB channels (0 - 1) are seen with the deficiency
+     */
+    pub fn simulate_linear(&self, rgb: (f32, f32, f32)) -> (f32, f32, f32) {
+        let m = match self {
+            Deficiency::Protanopia => &PROTANOPIA,
+            Deficiency::Deuteranopia => &DEUTERANOPIA,
+            Deficiency::Tritanopia => &TRITANOPIA,
+            Deficiency::Achromatopsia => &ACHROMATOPSIA,
+        };
+        let (r, g, b) = mul_matrix(m, rgb);
+        let clip = |val: f32| val.max(0.0).min(1.0);
+        return (clip(r), clip(g), clip(b));
+    }
+}
+
+impl FromStr for Deficiency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "protanopia" => Ok(Deficiency::Protanopia),
+            "deuteranopia" => Ok(Deficiency::Deuteranopia),
+            "tritanopia" => Ok(Deficiency::Tritanopia),
+            "achromatopsia" => Ok(Deficiency::Achromatopsia),
+            _ => Err(format!("Unknown color vision deficiency '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Deficiency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Deficiency::Protanopia => "Protanopia",
+            Deficiency::Deuteranopia => "Deuteranopia",
+            Deficiency::Tritanopia => "Tritanopia",
+            Deficiency::Achromatopsia => "Achromatopsia",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 // RGB -> XYZ -> LAB conversions and vice versa from https://www.easyrgb.com/en/math.php
 // Continuity correction of the function from http://www.brucelindbloom.com/index.html?LContinuity.html
 const KAPPA: f32 = 24389.0 / 27.0;
 const EPSILON: f32 = 216.0 / 24389.0;
 const EPSILON_CUBE_ROOT: f32 = 0.20689655172413796;
 
-fn map_rgb_xyz(val: f32) -> f32 {
-    return (val / 255.0).powf(2.19921875) * 100.0;
+// Rec. 2020 transfer function constants
+const REC2020_ALPHA: f32 = 1.09929682680944;
+const REC2020_BETA: f32 = 0.018053968510807;
+
+// Linear RGB -> XYZ matrices of the working spaces from http://www.brucelindbloom.com/index.html?Eqn_RGB_XYZ_Matrix.html
+// and https://www.w3.org/TR/css-color-4/#color-conversion-code
+const SRGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.4124564, 0.3575761, 0.1804375],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.0193339, 0.1191920, 0.9503041],
+];
+const XYZ_TO_SRGB: [[f32; 3]; 3] = [
+    [3.2404542, -1.5371385, -0.4985314],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0556434, -0.2040259, 1.0572252],
+];
+const ADOBE_RGB_TO_XYZ: [[f32; 3]; 3] = [
+    [0.5767309, 0.1855540, 0.1881852],
+    [0.2973769, 0.6273491, 0.0752741],
+    [0.0270343, 0.0706872, 0.9911085],
+];
+const XYZ_TO_ADOBE_RGB: [[f32; 3]; 3] = [
+    [2.0413690, -0.5649464, -0.3446944],
+    [-0.9692660, 1.8760108, 0.0415560],
+    [0.0134474, -0.1183897, 1.0154096],
+];
+const DISPLAY_P3_TO_XYZ: [[f32; 3]; 3] = [
+    [0.48657095, 0.26566769, 0.19821729],
+    [0.22897456, 0.69173852, 0.07928691],
+    [0.0, 0.04511338, 1.04394437],
+];
+const XYZ_TO_DISPLAY_P3: [[f32; 3]; 3] = [
+    [2.49349691, -0.93138362, -0.40271078],
+    [-0.82948897, 1.76266406, 0.02362469],
+    [0.03584583, -0.07617239, 0.95688452],
+];
+const REC2020_TO_XYZ: [[f32; 3]; 3] = [
+    [0.63695805, 0.14461690, 0.16888098],
+    [0.26270021, 0.67799807, 0.05930172],
+    [0.0, 0.02807269, 1.06098506],
+];
+const XYZ_TO_REC2020: [[f32; 3]; 3] = [
+    [1.71665119, -0.35567078, -0.25336628],
+    [-0.66668435, 1.61648124, 0.01576855],
+    [0.01763986, -0.04277061, 0.94210312],
+];
+
+// Bradford cone response matrices from http://www.brucelindbloom.com/index.html?Eqn_ChromAdapt.html
+const BRADFORD: [[f32; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+const BRADFORD_INV: [[f32; 3]; 3] = [
+    [0.9869929, -0.1470543, 0.1599627],
+    [0.4323053, 0.5183603, 0.0492912],
+    [-0.0085287, 0.0400428, 0.9684867],
+];
+
+// XYZ (D65) -> OKLab matrices from https://bottosson.github.io/posts/oklab/
+const XYZ_TO_LMS: [[f32; 3]; 3] = [
+    [0.8189330101, 0.3618667424, -0.1288597137],
+    [0.0329845436, 0.9293118715, 0.0361456387],
+    [0.0482003018, 0.2643662691, 0.6338517070],
+];
+const LMS_TO_XYZ: [[f32; 3]; 3] = [
+    [1.2270138511, -0.5577999807, 0.2812561490],
+    [-0.0405801784, 1.1122568696, -0.0716766787],
+    [-0.0763812845, -0.4214819784, 1.5861632204],
+];
+const LMS_TO_OKLAB: [[f32; 3]; 3] = [
+    [0.2104542553, 0.7936177850, -0.0040720468],
+    [1.9779984951, -2.4285922050, 0.4505937099],
+    [0.0259040371, 0.7827717662, -0.8086757660],
+];
+const OKLAB_TO_LMS: [[f32; 3]; 3] = [
+    [1.0, 0.3963377774, 0.2158037573],
+    [1.0, -0.1055613458, -0.0638541728],
+    [1.0, -0.0894841775, -1.2914855480],
+];
+
+// Constants of APCA 0.0.98G-4g from https://github.com/Myndex/apca-w3
+const APCA_EXPONENT: f32 = 2.4;
+const APCA_COEFFICIENTS: (f32, f32, f32) = (0.2126729, 0.7151522, 0.0721750);
+const APCA_BLACK_THRESHOLD: f32 = 0.022;
+const APCA_BLACK_CLAMP: f32 = 1.414;
+const APCA_NORMAL_BG: f32 = 0.56;
+const APCA_NORMAL_TEXT: f32 = 0.57;
+const APCA_REVERSE_TEXT: f32 = 0.62;
+const APCA_REVERSE_BG: f32 = 0.65;
+const APCA_SCALE: f32 = 1.14;
+const APCA_OFFSET: f32 = 0.027;
+const APCA_MIN_DELTA_Y: f32 = 0.0005;
+const APCA_LOW_CLIP: f32 = 0.1;
+
+// Simulation matrices of dichromacy in linear sRGB (severity 1.0) from Machado, Oliveira and Fernandes (2009)
+// https://www.inf.ufrgs.br/~oliveira/pubs_files/CVD_Simulation/CVD_Simulation.html
+const PROTANOPIA: [[f32; 3]; 3] = [
+    [0.152286, 1.052583, -0.204868],
+    [0.114503, 0.786281, 0.099216],
+    [-0.003882, -0.048116, 1.051998],
+];
+const DEUTERANOPIA: [[f32; 3]; 3] = [
+    [0.367322, 0.860646, -0.227968],
+    [0.280085, 0.672501, 0.047413],
+    [-0.011820, 0.042940, 0.968881],
+];
+const TRITANOPIA: [[f32; 3]; 3] = [
+    [1.255528, -0.076749, -0.178779],
+    [-0.078411, 0.930809, 0.147602],
+    [0.004733, 0.691367, 0.303900],
+];
+// Achromatopsia keeps only the luminance (Y) of sRGB
+const ACHROMATOPSIA: [[f32; 3]; 3] = [
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.2126729, 0.7151522, 0.0721750],
+    [0.2126729, 0.7151522, 0.0721750],
+];
+
+fn mul_matrix(m: &[[f32; 3]; 3], v: (f32, f32, f32)) -> (f32, f32, f32) {
+    return (
+        m[0][0]*v.0 + m[0][1]*v.1 + m[0][2]*v.2,
+        m[1][0]*v.0 + m[1][1]*v.1 + m[1][2]*v.2,
+        m[2][0]*v.0 + m[2][1]*v.1 + m[2][2]*v.2
+    );
 }
 
-fn map_xyz_rgb(val: f32) -> u8 {
-    (val.powf(1.0 / 2.19921875) * 255.0) as u8
+impl WhitePoint {
+    /**
+     * XYZ values of the white point
+     */
+    pub fn xyz(&self) -> (f32, f32, f32) {
+        match self {
+            WhitePoint::D50 => (96.422, 100.0, 82.521),
+            WhitePoint::D65 => (95.047, 100.0, 108.883),
+            WhitePoint::Custom(x, y, z) => (*x, *y, *z),
+        }
+    }
+
+    /**
+     * Adapts XYZ values relative to this white point to another white point using the Bradford transform
+     */
+    pub fn adapt(&self, xyz: (f32, f32, f32), to: WhitePoint) -> (f32, f32, f32) {
+        if *self == to {
+            return xyz;
+        }
+
+        let src = mul_matrix(&BRADFORD, self.xyz());
+        let dst = mul_matrix(&BRADFORD, to.xyz());
+        let cone = mul_matrix(&BRADFORD, xyz);
+
+        return mul_matrix(&BRADFORD_INV, (
+            cone.0 * dst.0 / src.0,
+            cone.1 * dst.1 / src.1,
+            cone.2 * dst.2 / src.2,
+        ));
+    }
+}
+
+impl RGBSpace {
+    /**
+     * Converts an encoded channel value (0 - 1) of the working space to linear light
+     */
+    pub fn to_linear(&self, val: f32) -> f32 {
+        match self {
+            RGBSpace::SRGB | RGBSpace::DisplayP3 => {
+                if val <= 0.04045 {
+                    val / 12.92
+                } else {
+                    ((val + 0.055) / 1.055).powf(2.4)
+                }
+            },
+            RGBSpace::AdobeRGB => val.max(0.0).powf(2.19921875),
+            RGBSpace::Rec2020 => {
+                if val < REC2020_BETA * 4.5 {
+                    val / 4.5
+                } else {
+                    ((val + REC2020_ALPHA - 1.0) / REC2020_ALPHA).powf(1.0 / 0.45)
+                }
+            },
+        }
+    }
+
+    /**
+     * Converts linear light (0 - 1) to the encoded channel value of the working space
+     */
+    pub fn from_linear(&self, val: f32) -> f32 {
+        match self {
+            RGBSpace::SRGB | RGBSpace::DisplayP3 => {
+                if val <= 0.0031308 {
+                    val * 12.92
+                } else {
+                    1.055 * val.powf(1.0 / 2.4) - 0.055
+                }
+            },
+            RGBSpace::AdobeRGB => val.max(0.0).powf(1.0 / 2.19921875),
+            RGBSpace::Rec2020 => {
+                if val < REC2020_BETA {
+                    val * 4.5
+                } else {
+                    REC2020_ALPHA * val.powf(0.45) - (REC2020_ALPHA - 1.0)
+                }
+            },
+        }
+    }
+
+    /**
+     * Converts linear RGB (0 - 1) of the working space to XYZ (0 - 100)
+     */
+    pub fn linear_to_xyz(&self, rgb: (f32, f32, f32)) -> (f32, f32, f32) {
+        let m = match self {
+            RGBSpace::SRGB => &SRGB_TO_XYZ,
+            RGBSpace::AdobeRGB => &ADOBE_RGB_TO_XYZ,
+            RGBSpace::DisplayP3 => &DISPLAY_P3_TO_XYZ,
+            RGBSpace::Rec2020 => &REC2020_TO_XYZ,
+        };
+        let xyz = mul_matrix(m, rgb);
+        return (xyz.0 * 100.0, xyz.1 * 100.0, xyz.2 * 100.0);
+    }
+
+    /**
+     * Converts XYZ (0 - 100) to linear RGB (0 - 1) of the working space
+     */
+    pub fn xyz_to_linear(&self, xyz: (f32, f32, f32)) -> (f32, f32, f32) {
+        let m = match self {
+            RGBSpace::SRGB => &XYZ_TO_SRGB,
+            RGBSpace::AdobeRGB => &XYZ_TO_ADOBE_RGB,
+            RGBSpace::DisplayP3 => &XYZ_TO_DISPLAY_P3,
+            RGBSpace::Rec2020 => &XYZ_TO_REC2020,
+        };
+        return mul_matrix(m, (xyz.0 / 100.0, xyz.1 / 100.0, xyz.2 / 100.0));
+    }
 }
 
+// Converts an encoded channel value (0 - 1) to 8-bit
+fn to_u8(val: f32) -> u8 {
+    (val.max(0.0).min(1.0) * 255.0).round() as u8
+}
 
 fn map_xyz_lab(val: f32) -> f32 {
     if val > EPSILON {
@@ -46,6 +502,83 @@ fn map_xyz_lab(val: f32) -> f32 {
 }
 
 impl RGB {
+    /**
+     * Relative luminance (0 - 1) of the color as defined by WCAG 2.x
+     */
+    pub fn luminance(&self) -> f32 {
+        let space = RGBSpace::SRGB;
+        let linear = |val: u8| space.to_linear(val as f32 / 255.0);
+        return 0.2126 * linear(self.r) + 0.7152 * linear(self.g) + 0.0722 * linear(self.b);
+    }
+
+    /**
+     * WCAG 2.x contrast ratio (1 - 21) between two colors
+     */
+    pub fn contrast_ratio(&self, color: &RGB) -> f32 {
+        let (l1, l2) = (self.luminance(), color.luminance());
+        return (l1.max(l2) + 0.05) / (l1.min(l2) + 0.05);
+    }
+
+    // Screen luminance used by APCA, with a soft clamp of near black colors
+    fn apca_luminance(&self) -> f32 {
+        let channel = |val: u8| (val as f32 / 255.0).powf(APCA_EXPONENT);
+        let y = APCA_COEFFICIENTS.0 * channel(self.r)
+            + APCA_COEFFICIENTS.1 * channel(self.g)
+            + APCA_COEFFICIENTS.2 * channel(self.b);
+
+        if y < APCA_BLACK_THRESHOLD {
+            return y + (APCA_BLACK_THRESHOLD - y).powf(APCA_BLACK_CLAMP);
+        }
+        return y;
+    }
+
+    /**
+     * APCA lightness contrast (Lc) of the color as text over the background
+     *
+     * Lc is positive for dark text on light backgrounds and negative for light text on dark backgrounds
+     */
+    pub fn apca_contrast(&self, background: &RGB) -> f32 {
+        let text_y = self.apca_luminance();
+        let bg_y = background.apca_luminance();
+        if (bg_y - text_y).abs() < APCA_MIN_DELTA_Y {
+            return 0.0;
+        }
+
+        let lc = if bg_y > text_y {
+            let sapc = (bg_y.powf(APCA_NORMAL_BG) - text_y.powf(APCA_NORMAL_TEXT)) * APCA_SCALE;
+            if sapc < APCA_LOW_CLIP { 0.0 } else { sapc - APCA_OFFSET }
+        } else {
+            let sapc = (bg_y.powf(APCA_REVERSE_BG) - text_y.powf(APCA_REVERSE_TEXT)) * APCA_SCALE;
+            if sapc > -APCA_LOW_CLIP { 0.0 } else { sapc + APCA_OFFSET }
+        };
+
+        return lc * 100.0;
+    }
+
+    /**
+     * Creates a color from a hex color code with an optional leading '#'
+     *
+     * Short forms and the alpha of 8-digit codes are accepted, the alpha is dropped. Use `str::parse` for other CSS colors.
+     */
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        return parse_hex(hex).ok().map(|(rgb, _)| rgb);
+    }
+
+    /**
+     * Simulates how the color is seen with a color vision deficiency
+     */
+    pub fn simulate(&self, deficiency: Deficiency) -> Self {
+        let space = RGBSpace::SRGB;
+        let linear = |val: u8| space.to_linear(val as f32 / 255.0);
+        let (r, g, b) = deficiency.simulate_linear((linear(self.r), linear(self.g), linear(self.b)));
+
+        return RGB {
+            r: to_u8(space.from_linear(r)),
+            g: to_u8(space.from_linear(g)),
+            b: to_u8(space.from_linear(b)),
+        };
+    }
+
     /**
      * Converts the color to the corresponding hex color code
      */
@@ -58,37 +591,152 @@ impl RGB {
     }
 
     /**
-     * Converts the color to the corresponding XYZ color space
+     * Converts the sRGB color to the corresponding XYZ color space
      */
     pub fn to_xyz(&self) -> (f32, f32, f32) {
-        let var_r = map_rgb_xyz(self.r.into());
-        let var_g = map_rgb_xyz(self.g.into());
-        let var_b = map_rgb_xyz(self.b.into());
-
-        return (
-            var_r*0.57667 + var_g*0.18555 + var_b*0.18819,
-            var_r*0.29738 + var_g*0.62735 + var_b*0.07527,
-            var_r*0.02703 + var_g*0.07069 + var_b*0.99110
-        );
+        return self.to_xyz_in(RGBSpace::SRGB);
+    }
+
+    /**
+     * Converts the color in the given RGB working space to the corresponding XYZ color space
+     */
+    pub fn to_xyz_in(&self, space: RGBSpace) -> (f32, f32, f32) {
+        return space.linear_to_xyz((
+            space.to_linear(self.r as f32 / 255.0),
+            space.to_linear(self.g as f32 / 255.0),
+            space.to_linear(self.b as f32 / 255.0),
+        ));
+    }
+
+    /**
+     * Creates a color in the given RGB working space from XYZ color space
+     *
+     * Channels outside the gamut of the space are clipped, use `gamut::map_to_gamut` to preserve the hue
+     */
+    pub fn from_xyz_in(xyz: (f32, f32, f32), space: RGBSpace) -> Self {
+        let (r, g, b) = space.xyz_to_linear(xyz);
+
+        return RGB {
+            r: to_u8(space.from_linear(r)),
+            g: to_u8(space.from_linear(g)),
+            b: to_u8(space.from_linear(b)),
+        };
+    }
+
+    /**
+     * Creates equivalent color in the given RGB working space from LAB color
+     *
+     * Colors outside the gamut of the space are mapped into it by reducing their chroma
+     */
+    pub fn from_lab_in(color: &LAB, space: RGBSpace) -> Self {
+        return map_to_gamut(color, space).0;
+    }
+
+    /**
+     * Creates equivalent color in the given RGB working space from LAB color relative to a white point
+     */
+    pub fn from_lab_with(color: &LAB, space: RGBSpace, white: WhitePoint) -> Self {
+        let xyz = white.adapt(color.to_xyz_with(white), WhitePoint::D65);
+        return Self::from_lab_in(&LAB::from_xyz(xyz), space);
     }
 }
 
-impl From<&LAB> for RGB {
+impl RGBA {
+    /**
+     * Color without the alpha channel
+     */
+    pub fn rgb(&self) -> RGB {
+        return RGB { r: self.r, g: self.g, b: self.b };
+    }
 
     /**
-     * Creates equivalent RGB color from LAB color
+     * Alpha of the color from 0 to 1
      */
-    fn from(color: &LAB) -> Self {
-        let xyz = color.to_xyz();
-        let var_x = xyz.0 / 100.0;
-        let var_y = xyz.1 / 100.0;
-        let var_z = xyz.2 / 100.0;
-        
+    pub fn alpha(&self) -> f32 {
+        return self.a as f32 / 255.0;
+    }
+
+    /**
+     * Converts the straight color to a color with its channels premultiplied by alpha
+     */
+    pub fn premultiply(&self) -> Self {
+        let a = self.alpha();
+        let mul = |val: u8| (val as f32 * a).round() as u8;
+
+        return RGBA { r: mul(self.r), g: mul(self.g), b: mul(self.b), a: self.a };
+    }
+
+    /**
+     * Converts the premultiplied color back to a straight color
+     */
+    pub fn unpremultiply(&self) -> Self {
+        if self.a == 0 {
+            return RGBA { r: 0, g: 0, b: 0, a: 0 };
+        }
+
+        let a = self.alpha();
+        let div = |val: u8| (val as f32 / a).round().min(255.0) as u8;
+
+        return RGBA { r: div(self.r), g: div(self.g), b: div(self.b), a: self.a };
+    }
+
+    /**
+     * Composites the straight color over an opaque background color
+     */
+    pub fn composite(&self, background: &RGB) -> RGB {
+        let a = self.alpha();
+        let mix = |fg: u8, bg: u8| (fg as f32 * a + bg as f32 * (1.0 - a)).round() as u8;
+
         return RGB {
-            r: map_xyz_rgb(var_x*2.04137 + var_y*-0.56495 + var_z*-0.34469),
-            g: map_xyz_rgb(var_x*-0.96927 + var_y*1.87601 + var_z*0.04156),
-            b: map_xyz_rgb(var_x*0.01345 + var_y*-0.11839 + var_z*1.01541)
+            r: mix(self.r, background.r),
+            g: mix(self.g, background.g),
+            b: mix(self.b, background.b),
+        };
+    }
+
+    /**
+     * Recovers the straight color which gives `color` when composited with `alpha` (0 - 1) over the background
+     */
+    pub fn uncomposite(color: &RGB, alpha: f32, background: &RGB) -> Self {
+        let a = alpha.max(0.0).min(1.0);
+        let a8 = (a * 255.0).round() as u8;
+        if a8 == 0 {
+            return RGBA { r: 0, g: 0, b: 0, a: 0 };
+        }
+
+        let unmix = |fg: u8, bg: u8| {
+            ((fg as f32 - bg as f32 * (1.0 - a)) / a).round().max(0.0).min(255.0) as u8
         };
+
+        return RGBA {
+            r: unmix(color.r, background.r),
+            g: unmix(color.g, background.g),
+            b: unmix(color.b, background.b),
+            a: a8,
+        };
+    }
+
+    /**
+     * Converts the color to the corresponding 8-digit hex color code
+     */
+    pub fn hex(&self) -> String {
+        return format!("#{:02X}{:02X}{:02X}{:02X}", self.r, self.g, self.b, self.a);
+    }
+}
+
+impl From<&RGB> for RGBA {
+    fn from(color: &RGB) -> Self {
+        return RGBA { r: color.r, g: color.g, b: color.b, a: 255 };
+    }
+}
+
+impl From<&LAB> for RGB {
+
+    /**
+     * Creates equivalent sRGB color from LAB color
+     */
+    fn from(color: &LAB) -> Self {
+        return RGB::from_lab_in(color, RGBSpace::SRGB);
     }
 }
 
@@ -153,6 +801,69 @@ impl LAB {
         })
     }
 
+    /**
+     * Helper function to create a LAB color from 16-bit sRGB values
+     */
+    pub fn from_rgb16(r: u16, g: u16, b: u16) -> Self {
+        let encoded = |val: u16| val as f32 / 65535.0;
+        return Self::from_encoded((encoded(r), encoded(g), encoded(b)), RGBSpace::SRGB);
+    }
+
+    /**
+     * Creates a LAB color from encoded RGB channels (0 - 1) of the given working space
+     */
+    pub fn from_encoded(rgb: (f32, f32, f32), space: RGBSpace) -> Self {
+        let linear = (space.to_linear(rgb.0), space.to_linear(rgb.1), space.to_linear(rgb.2));
+        return Self::from_linear(linear, space);
+    }
+
+    /**
+     * Creates a LAB color from linear RGB channels of the given working space
+     *
+     * Channels above 1 (like in HDR images) are brighter than the white point and have a lightness above 100
+     */
+    pub fn from_linear(rgb: (f32, f32, f32), space: RGBSpace) -> Self {
+        return Self::from_xyz(space.linear_to_xyz(rgb));
+    }
+
+    /**
+     * Helper function to create a LAB color from RGB values in the given working space
+     */
+    pub fn from_rgb_in(color: &RGB, space: RGBSpace) -> Self {
+        return Self::from_xyz(color.to_xyz_in(space));
+    }
+
+    /**
+     * Helper function to create a LAB color relative to a white point from RGB values in the given working space
+     */
+    pub fn from_rgb_with(color: &RGB, space: RGBSpace, white: WhitePoint) -> Self {
+        let xyz = WhitePoint::D65.adapt(color.to_xyz_in(space), white);
+        return Self::from_xyz_with(xyz, white);
+    }
+
+    /**
+     * Creates a LAB color from XYZ color space (0 - 100) relative to D65 white point
+     */
+    pub fn from_xyz(xyz: (f32, f32, f32)) -> Self {
+        return Self::from_xyz_with(xyz, WhitePoint::D65);
+    }
+
+    /**
+     * Creates a LAB color from XYZ color space (0 - 100) relative to a white point
+     */
+    pub fn from_xyz_with(xyz: (f32, f32, f32), white: WhitePoint) -> Self {
+        let (ref_x, ref_y, ref_z) = white.xyz();
+        let var_x = map_xyz_lab(xyz.0 / ref_x);
+        let var_y = map_xyz_lab(xyz.1 / ref_y);
+        let var_z = map_xyz_lab(xyz.2 / ref_z);
+
+        return LAB {
+            l: 116.0 * var_y - 16.0,
+            a: 500.0 * (var_x - var_y),
+            b: 200.0 * (var_y - var_z)
+        };
+    }
+
     /**
      * Calculates the chroma of the color 
      */
@@ -160,6 +871,18 @@ impl LAB {
         (self.a.powi(2) + self.b.powi(2)).sqrt()
     }
 
+    /**
+     * Simulates how the color (relative to D65) is seen with a color vision deficiency
+     *
+     * Colors outside the sRGB gamut are clipped to it before the simulation
+     */
+    pub fn simulate(&self, deficiency: Deficiency) -> Self {
+        let space = RGBSpace::SRGB;
+        let clip = |val: f32| val.max(0.0).min(1.0);
+        let (r, g, b) = space.xyz_to_linear(self.to_xyz());
+        return Self::from_linear(deficiency.simulate_linear((clip(r), clip(g), clip(b))), space);
+    }
+
     /**
      * Finds the index and distance from nearest color from a group of colors
      */
@@ -198,7 +921,17 @@ impl LAB {
         return ( xdl.powi(2) + xdc.powi(2) + xdh.powi(2) ).sqrt();
     }
 
+    /**
+     * Converts the color to XYZ color space (0 - 100) relative to D65 white point
+     */
     pub fn to_xyz(&self) -> (f32, f32, f32) {
+        return self.to_xyz_with(WhitePoint::D65);
+    }
+
+    /**
+     * Converts the color to XYZ color space (0 - 100) relative to a white point
+     */
+    pub fn to_xyz_with(&self, white: WhitePoint) -> (f32, f32, f32) {
         let mut var_y = (self.l + 16.0) / 116.0;
         let mut var_x = self.a / 500.0 + var_y;
         let mut var_z = var_y - self.b / 200.0;
@@ -219,7 +952,16 @@ impl LAB {
             var_z = ((var_z * 116.0) - 16.0) / KAPPA;
         }
 
-        return (var_x * 95.047, var_y * 100.0, var_z * 108.883);
+        let (ref_x, ref_y, ref_z) = white.xyz();
+        return (var_x * ref_x, var_y * ref_y, var_z * ref_z);
+    }
+
+    /**
+     * Converts the color relative to a white point into an equivalent color relative to another white point
+     * using Bradford chromatic adaptation
+     */
+    pub fn adapt(&self, from: WhitePoint, to: WhitePoint) -> Self {
+        return Self::from_xyz_with(from.adapt(self.to_xyz_with(from), to), to);
     }
 
 }
@@ -227,20 +969,10 @@ impl LAB {
 impl From<&RGB> for LAB {
 
     /**
-     * Creates equivalent LAB color from RGB color
+     * Creates equivalent LAB color from sRGB color
      */
     fn from(color: &RGB) -> Self {
-        let xyz = color.to_xyz();
-
-        let var_x = map_xyz_lab(xyz.0 / 95.047);
-        let var_y = map_xyz_lab(xyz.1 / 100.0);
-        let var_z = map_xyz_lab(xyz.2 / 108.883);
-
-        return LAB {
-            l: 116.0 * var_y - 16.0,
-            a: 500.0 * (var_x - var_y),
-            b: 200.0 * (var_y - var_z)
-        };
+        return LAB::from_rgb_in(color, RGBSpace::SRGB);
     }
 }
 
@@ -298,6 +1030,326 @@ impl From<&LAB> for HSL {
     }
 }
 
+impl From<&LAB> for LCH {
+    fn from(color: &LAB) -> Self {
+        let mut h = color.b.atan2(color.a).to_degrees();
+        if h < 0.0 {
+            h += 360.0;
+        }
+
+        return LCH {
+            l: color.l,
+            c: color.chroma(),
+            h: h
+        };
+    }
+}
+
+impl From<&LCH> for LAB {
+    fn from(color: &LCH) -> Self {
+        let h = color.h.to_radians();
+        return LAB {
+            l: color.l,
+            a: color.c * h.cos(),
+            b: color.c * h.sin()
+        };
+    }
+}
+
+impl From<&RGB> for LCH {
+    fn from(color: &RGB) -> Self {
+        return LCH::from(&LAB::from(color));
+    }
+}
+
+impl From<&LCH> for RGB {
+    fn from(color: &LCH) -> Self {
+        return RGB::from(&LAB::from(color));
+    }
+}
+
+impl From<&RGB> for HSV {
+    fn from(color: &RGB) -> Self {
+        let var_r = color.r as f32 / 255.0;
+        let var_g = color.g as f32 / 255.0;
+        let var_b = color.b as f32 / 255.0;
+
+        let var_max = var_r.max(var_g.max(var_b));
+        let var_min = var_r.min(var_g.min(var_b));
+
+        return HSV {
+            // Hue is the same in HSL and HSV
+            h: HSL::from(color).h,
+            s: if var_max == 0.0 { 0.0 } else { (var_max - var_min) / var_max },
+            v: var_max
+        };
+    }
+}
+
+impl From<&HSV> for RGB {
+    fn from(color: &HSV) -> Self {
+        let h = (color.h * 6.0) % 6.0;
+        let c = color.v * color.s;
+        let x = c * (1.0 - ((h % 2.0) - 1.0).abs());
+        let m = color.v - c;
+
+        let (r, g, b) = if h < 1.0 {
+            (c, x, 0.0)
+        } else if h < 2.0 {
+            (x, c, 0.0)
+        } else if h < 3.0 {
+            (0.0, c, x)
+        } else if h < 4.0 {
+            (0.0, x, c)
+        } else if h < 5.0 {
+            (x, 0.0, c)
+        } else {
+            (c, 0.0, x)
+        };
+
+        return RGB {
+            r: to_u8(r + m),
+            g: to_u8(g + m),
+            b: to_u8(b + m)
+        };
+    }
+}
+
+impl From<&LAB> for HSV {
+    fn from(color: &LAB) -> Self {
+        return HSV::from(&RGB::from(color));
+    }
+}
+
+impl From<&RGB> for HWB {
+    fn from(color: &RGB) -> Self {
+        let hsv = HSV::from(color);
+        return HWB {
+            h: hsv.h,
+            w: (1.0 - hsv.s) * hsv.v,
+            b: 1.0 - hsv.v
+        };
+    }
+}
+
+impl From<&HWB> for RGB {
+    fn from(color: &HWB) -> Self {
+        // Whiteness and blackness adding upto more than 1 result in a shade of gray
+        if color.w + color.b >= 1.0 {
+            let gray = to_u8(color.w / (color.w + color.b));
+            return RGB { r: gray, g: gray, b: gray };
+        }
+
+        let v = 1.0 - color.b;
+        return RGB::from(&HSV {
+            h: color.h,
+            s: 1.0 - color.w / v,
+            v: v
+        });
+    }
+}
+
+impl From<&LAB> for HWB {
+    fn from(color: &LAB) -> Self {
+        return HWB::from(&RGB::from(color));
+    }
+}
+
+impl CMYK {
+    /**
+     * Creates a CMYK color from RGB color using the black generation
+     *
+     * The amount of black ink replaces an equal amount of cyan, magenta and yellow inks
+     * so that the color looks the same irrespective of the black generation
+     */
+    pub fn from_rgb_with(color: &RGB, black: BlackGeneration) -> Self {
+        let c = 1.0 - color.r as f32 / 255.0;
+        let m = 1.0 - color.g as f32 / 255.0;
+        let y = 1.0 - color.b as f32 / 255.0;
+        let gray = c.min(m.min(y));
+
+        let k = match black {
+            BlackGeneration::Naive => gray,
+            BlackGeneration::GCR(amount) => amount.max(0.0).min(1.0) * gray,
+            BlackGeneration::UCR(amount) => {
+                // Neutral colors have similar amounts of each ink
+                let neutrality = 1.0 - (c.max(m.max(y)) - gray);
+                amount.max(0.0).min(1.0) * gray * neutrality
+            },
+        };
+
+        // Pure black only needs the black ink
+        if k >= 1.0 {
+            return CMYK { c: 0.0, m: 0.0, y: 0.0, k: 1.0 };
+        }
+
+        return CMYK {
+            c: (c - k) / (1.0 - k),
+            m: (m - k) / (1.0 - k),
+            y: (y - k) / (1.0 - k),
+            k: k
+        };
+    }
+}
+
+impl From<&RGB> for CMYK {
+    fn from(color: &RGB) -> Self {
+        return CMYK::from_rgb_with(color, BlackGeneration::Naive);
+    }
+}
+
+impl From<&LAB> for CMYK {
+    fn from(color: &LAB) -> Self {
+        return CMYK::from(&RGB::from(color));
+    }
+}
+
+impl From<&CMYK> for RGB {
+    fn from(color: &CMYK) -> Self {
+        return RGB {
+            r: to_u8((1.0 - color.c) * (1.0 - color.k)),
+            g: to_u8((1.0 - color.m) * (1.0 - color.k)),
+            b: to_u8((1.0 - color.y) * (1.0 - color.k))
+        };
+    }
+}
+
+impl OKLAB {
+
+    /**
+     * Creates an OKLab color from XYZ color space (0 - 100) relative to D65 white point
+     */
+    pub fn from_xyz(xyz: (f32, f32, f32)) -> Self {
+        let lms = mul_matrix(&XYZ_TO_LMS, (xyz.0 / 100.0, xyz.1 / 100.0, xyz.2 / 100.0));
+        let lab = mul_matrix(&LMS_TO_OKLAB, (lms.0.cbrt(), lms.1.cbrt(), lms.2.cbrt()));
+
+        return OKLAB {
+            l: lab.0,
+            a: lab.1,
+            b: lab.2
+        };
+    }
+
+    /**
+     * Converts the color to XYZ color space (0 - 100) relative to D65 white point
+     */
+    pub fn to_xyz(&self) -> (f32, f32, f32) {
+        let lms = mul_matrix(&OKLAB_TO_LMS, (self.l, self.a, self.b));
+        let xyz = mul_matrix(&LMS_TO_XYZ, (lms.0.powi(3), lms.1.powi(3), lms.2.powi(3)));
+        return (xyz.0 * 100.0, xyz.1 * 100.0, xyz.2 * 100.0);
+    }
+
+    /**
+     * Calculates the Euclidean distance (Delta E OK) between two colors
+     */
+    pub fn distance(&self, color: &OKLAB) -> f32 {
+        return ((self.l - color.l).powi(2) + (self.a - color.a).powi(2) + (self.b - color.b).powi(2)).sqrt();
+    }
+}
+
+impl From<&LAB> for OKLAB {
+    fn from(color: &LAB) -> Self {
+        return OKLAB::from_xyz(color.to_xyz());
+    }
+}
+
+impl From<&RGB> for OKLAB {
+    fn from(color: &RGB) -> Self {
+        return OKLAB::from_xyz(color.to_xyz());
+    }
+}
+
+impl From<&HSL> for OKLAB {
+    fn from(color: &HSL) -> Self {
+        return OKLAB::from(&RGB::from(color));
+    }
+}
+
+impl From<&OKLCH> for OKLAB {
+    fn from(color: &OKLCH) -> Self {
+        let h = color.h.to_radians();
+        return OKLAB {
+            l: color.l,
+            a: color.c * h.cos(),
+            b: color.c * h.sin()
+        };
+    }
+}
+
+impl From<&OKLAB> for LAB {
+    fn from(color: &OKLAB) -> Self {
+        return LAB::from_xyz(color.to_xyz());
+    }
+}
+
+impl From<&OKLAB> for RGB {
+    /**
+     * Creates equivalent sRGB color from OKLab color
+     *
+     * Colors outside the sRGB gamut are mapped into it by reducing their chroma
+     */
+    fn from(color: &OKLAB) -> Self {
+        return RGB::from_lab_in(&LAB::from(color), RGBSpace::SRGB);
+    }
+}
+
+impl From<&OKLAB> for HSL {
+    fn from(color: &OKLAB) -> Self {
+        return HSL::from(&RGB::from(color));
+    }
+}
+
+impl From<&OKLAB> for OKLCH {
+    fn from(color: &OKLAB) -> Self {
+        let mut h = color.b.atan2(color.a).to_degrees();
+        if h < 0.0 {
+            h += 360.0;
+        }
+
+        return OKLCH {
+            l: color.l,
+            c: (color.a.powi(2) + color.b.powi(2)).sqrt(),
+            h: h
+        };
+    }
+}
+
+impl From<&LAB> for OKLCH {
+    fn from(color: &LAB) -> Self {
+        return OKLCH::from(&OKLAB::from(color));
+    }
+}
+
+impl From<&RGB> for OKLCH {
+    fn from(color: &RGB) -> Self {
+        return OKLCH::from(&OKLAB::from(color));
+    }
+}
+
+impl From<&HSL> for OKLCH {
+    fn from(color: &HSL) -> Self {
+        return OKLCH::from(&OKLAB::from(color));
+    }
+}
+
+impl From<&OKLCH> for LAB {
+    fn from(color: &OKLCH) -> Self {
+        return LAB::from(&OKLAB::from(color));
+    }
+}
+
+impl From<&OKLCH> for RGB {
+    fn from(color: &OKLCH) -> Self {
+        return RGB::from(&OKLAB::from(color));
+    }
+}
+
+impl From<&OKLCH> for HSL {
+    fn from(color: &OKLCH) -> Self {
+        return HSL::from(&OKLAB::from(color));
+    }
+}
+
 impl PartialEq for LAB {
     fn eq(&self, other: &Self) -> bool {
         return !(self.l != other.l || self.a != other.a || self.b != other.b);
@@ -310,20 +1362,265 @@ impl PartialEq for RGB {
     }
 }
 
+impl PartialEq for RGBA {
+    fn eq(&self, other: &Self) -> bool {
+        return self.rgb() == other.rgb() && self.a == other.a;
+    }
+}
+
 impl fmt::Display for LAB {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "lab({}, {}, {})", self.l, self.a, self.b)
+        write!(f, "{}", self.to_css(&Format::default()))
     }
 }
 
 impl fmt::Display for HSL {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "hsl({}, {}%, {}%)", self.h * 360.0, self.s * 100.0, self.l * 100.0)
+        write!(f, "{}", self.to_css(&Format::default()))
     }
 }
 
 impl fmt::Display for RGB {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "rgb({}, {}, {})", self.r, self.g, self.b)
+        write!(f, "{}", self.to_css(&Format::default()))
+    }
+}
+
+impl fmt::Display for RGBA {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_css(&Format::default()))
+    }
+}
+
+impl fmt::Display for LCH {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_css(&Format::default()))
+    }
+}
+
+impl fmt::Display for HSV {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", crate::format::hsv(self, &Format::default()))
+    }
+}
+
+impl fmt::Display for HWB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_css(&Format::default()))
     }
-}
\ No newline at end of file
+}
+
+impl fmt::Display for CMYK {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_css(&Format::default()))
+    }
+}
+
+impl fmt::Display for OKLAB {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_css(&Format::default()))
+    }
+}
+
+impl fmt::Display for OKLCH {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_css(&Format::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: (f32, f32, f32), expected: (f32, f32, f32), tolerance: f32) {
+        let close = (actual.0 - expected.0).abs() <= tolerance
+            && (actual.1 - expected.1).abs() <= tolerance
+            && (actual.2 - expected.2).abs() <= tolerance;
+        assert!(close, "{:?} is not close to {:?}", actual, expected);
+    }
+
+    #[test]
+    fn converts_srgb_to_lab() {
+        let lab = |r, g, b| {
+            let color = LAB::from_rgb(r, g, b);
+            (color.l, color.a, color.b)
+        };
+        assert_close(lab(255, 0, 0), (53.2408, 80.0925, 67.2032), 0.01);
+        assert_close(lab(0, 0, 255), (32.2970, 79.1875, -107.8602), 0.01);
+        assert_close(lab(255, 255, 255), (100.0, 0.0, 0.0), 0.01);
+        assert_close(lab(0, 0, 0), (0.0, 0.0, 0.0), 0.01);
+        assert_close(RGB { r: 255, g: 0, b: 0 }.to_xyz(), (41.2456, 21.2673, 1.9334), 0.01);
+    }
+
+    #[test]
+    fn round_trips_every_channel_value_through_lab() {
+        // Every value of each channel is combined with a spread of values of the other channels
+        let spread: Vec<u8> = (0..=255).step_by(15).collect();
+        for val in 0..=255u8 {
+            for &x in spread.iter() {
+                for &y in spread.iter() {
+                    for rgb in [
+                        RGB { r: val, g: x, b: y },
+                        RGB { r: x, g: val, b: y },
+                        RGB { r: x, g: y, b: val },
+                    ].iter() {
+                        assert_eq!(RGB::from(&LAB::from(rgb)).hex(), rgb.hex());
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trips_through_rgb_spaces() {
+        let spaces = [RGBSpace::SRGB, RGBSpace::AdobeRGB, RGBSpace::DisplayP3, RGBSpace::Rec2020];
+        for space in spaces.iter() {
+            // White of every space is D65
+            assert_close(space.linear_to_xyz((1.0, 1.0, 1.0)), WhitePoint::D65.xyz(), 0.05);
+
+            for val in 0..=255u8 {
+                let encoded = val as f32 / 255.0;
+                assert!((space.from_linear(space.to_linear(encoded)) - encoded).abs() < 0.0001);
+
+                let rgb = RGB { r: val, g: 255 - val, b: val / 2 };
+                assert_eq!(RGB::from_xyz_in(rgb.to_xyz_in(*space), *space).hex(), rgb.hex());
+            }
+        }
+
+        // sRGB red is color(display-p3 0.9175 0.2003 0.1386)
+        let (r, g, b) = RGBSpace::DisplayP3.xyz_to_linear(RGB { r: 255, g: 0, b: 0 }.to_xyz());
+        let p3 = RGBSpace::DisplayP3;
+        assert_close((p3.from_linear(r), p3.from_linear(g), p3.from_linear(b)), (0.9175, 0.2003, 0.1386), 0.001);
+    }
+
+    #[test]
+    fn adapts_white_points_with_bradford() {
+        assert_close(WhitePoint::D65.adapt(WhitePoint::D65.xyz(), WhitePoint::D50), WhitePoint::D50.xyz(), 0.01);
+        assert_close(WhitePoint::D50.adapt(WhitePoint::D50.xyz(), WhitePoint::D65), WhitePoint::D65.xyz(), 0.01);
+
+        // sRGB red in XYZ relative to D50
+        let red = RGB { r: 255, g: 0, b: 0 }.to_xyz();
+        assert_close(WhitePoint::D65.adapt(red, WhitePoint::D50), (43.6075, 22.2504, 1.3932), 0.01);
+
+        let lab = LAB::from_rgb(255, 0, 0).adapt(WhitePoint::D65, WhitePoint::D50);
+        assert_close((lab.l, lab.a, lab.b), (54.2905, 80.8049, 69.8910), 0.01);
+    }
+
+    #[test]
+    fn converts_srgb_to_oklab() {
+        let oklab = |r, g, b| {
+            let color = OKLAB::from(&RGB { r, g, b });
+            (color.l, color.a, color.b)
+        };
+        assert_close(oklab(255, 0, 0), (0.62796, 0.22486, 0.12585), 0.0005);
+        assert_close(oklab(0, 255, 0), (0.86644, -0.23389, 0.17950), 0.0005);
+        assert_close(oklab(0, 0, 255), (0.45201, -0.03246, -0.31153), 0.0005);
+        assert_close(oklab(255, 255, 255), (1.0, 0.0, 0.0), 0.0005);
+    }
+
+    #[test]
+    fn calculates_wcag_contrast_ratio() {
+        let (black, white, gray) = (RGB { r: 0, g: 0, b: 0 }, RGB { r: 255, g: 255, b: 255 }, RGB { r: 136, g: 136, b: 136 });
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 0.01);
+        assert!((white.contrast_ratio(&black) - 21.0).abs() < 0.01);
+        assert!((gray.contrast_ratio(&white) - 3.54).abs() < 0.01);
+        assert!((white.contrast_ratio(&white) - 1.0).abs() < 0.01);
+        assert!((RGB { r: 255, g: 0, b: 0 }.luminance() - 0.2126).abs() < 0.0001);
+    }
+
+    #[test]
+    fn calculates_apca_contrast() {
+        // Reference values of APCA 0.0.98G
+        let (black, white, gray) = (RGB { r: 0, g: 0, b: 0 }, RGB { r: 255, g: 255, b: 255 }, RGB { r: 136, g: 136, b: 136 });
+        assert!((gray.apca_contrast(&white) - 63.06).abs() < 0.01);
+        assert!((white.apca_contrast(&gray) - -68.54).abs() < 0.01);
+        assert!((black.apca_contrast(&white) - 106.04).abs() < 0.01);
+        assert!((white.apca_contrast(&black) - -107.88).abs() < 0.01);
+        assert_eq!(gray.apca_contrast(&gray), 0.0);
+        assert_eq!(Contrast::APCA.calculate(&gray, &white), gray.apca_contrast(&white));
+    }
+
+    #[test]
+    fn simulates_color_vision_deficiencies() {
+        let (red, gray) = (RGB { r: 255, g: 0, b: 0 }, RGB { r: 119, g: 119, b: 119 });
+        for deficiency in Deficiency::ALL.iter() {
+            // Neutral colors are seen the same with every deficiency
+            assert_eq!(RGB { r: 255, g: 255, b: 255 }.simulate(*deficiency).hex(), "#FFFFFF");
+            assert_eq!(RGB { r: 0, g: 0, b: 0 }.simulate(*deficiency).hex(), "#000000");
+            assert_eq!(gray.simulate(*deficiency).hex(), gray.hex());
+        }
+
+        assert_eq!(red.simulate(Deficiency::Protanopia).hex(), "#6D5F00");
+        assert_eq!(red.simulate(Deficiency::Deuteranopia).hex(), "#A39000");
+        assert_eq!(red.simulate(Deficiency::Tritanopia).hex(), "#FF000F");
+        assert_eq!(red.simulate(Deficiency::Achromatopsia).hex(), "#7F7F7F");
+    }
+
+    #[test]
+    fn converts_rgb_to_cmyk() {
+        let cmyk = |rgb: RGB, black| {
+            let color = CMYK::from_rgb_with(&rgb, black);
+            (color.c, color.m, color.y, color.k)
+        };
+        let close = |actual: (f32, f32, f32, f32), expected: (f32, f32, f32, f32)| {
+            assert_close((actual.0, actual.1, actual.2), (expected.0, expected.1, expected.2), 0.001);
+            assert!((actual.3 - expected.3).abs() <= 0.001, "{:?} is not close to {:?}", actual, expected);
+        };
+        let black = || RGB { r: 0, g: 0, b: 0 };
+        let red = || RGB { r: 255, g: 0, b: 0 };
+        let orange = || RGB { r: 200, g: 100, b: 50 };
+
+        // Black is only black ink unless part of the gray component is kept in the other inks
+        close(cmyk(black(), BlackGeneration::Naive), (0.0, 0.0, 0.0, 1.0));
+        close(cmyk(black(), BlackGeneration::GCR(1.0)), (0.0, 0.0, 0.0, 1.0));
+        close(cmyk(black(), BlackGeneration::GCR(0.5)), (1.0, 1.0, 1.0, 0.5));
+        close(cmyk(black(), BlackGeneration::UCR(0.5)), (1.0, 1.0, 1.0, 0.5));
+
+        // Primaries have no gray component
+        for black in [BlackGeneration::Naive, BlackGeneration::GCR(0.5), BlackGeneration::UCR(0.5)].iter() {
+            close(cmyk(red(), *black), (0.0, 1.0, 1.0, 0.0));
+            close(cmyk(RGB { r: 0, g: 0, b: 255 }, *black), (1.0, 1.0, 0.0, 0.0));
+        }
+
+        // UCR removes less of the gray component from colorful colors than GCR
+        close(cmyk(orange(), BlackGeneration::Naive), (0.0, 0.5, 0.75, 0.2157));
+        close(cmyk(orange(), BlackGeneration::GCR(1.0)), (0.0, 0.5, 0.75, 0.2157));
+        close(cmyk(orange(), BlackGeneration::UCR(1.0)), (0.1394, 0.5698, 0.7849, 0.0888));
+    }
+
+    #[test]
+    fn round_trips_through_cmyk() {
+        let modes = [BlackGeneration::Naive, BlackGeneration::GCR(0.3), BlackGeneration::UCR(0.7)];
+        for mode in modes.iter() {
+            for val in (0..=255u8).step_by(5) {
+                for rgb in [RGB { r: val, g: val, b: val }, RGB { r: val, g: 255 - val, b: 40 }, RGB { r: 10, g: val, b: 200 }].iter() {
+                    assert_eq!(RGB::from(&CMYK::from_rgb_with(rgb, *mode)).hex(), rgb.hex());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn composites_rgba_colors() {
+        let white = RGB { r: 255, g: 255, b: 255 };
+        let blue = RGBA { r: 0, g: 0, b: 255, a: 128 };
+
+        assert_eq!(blue.composite(&white).hex(), "#7F7FFF");
+        assert_eq!(blue.composite(&RGB { r: 0, g: 0, b: 0 }).hex(), "#000080");
+        assert_eq!(RGBA { r: 10, g: 20, b: 30, a: 255 }.composite(&white).hex(), "#0A141E");
+        assert_eq!(RGBA { r: 10, g: 20, b: 30, a: 0 }.composite(&white).hex(), "#FFFFFF");
+
+        assert_eq!(RGBA::uncomposite(&blue.composite(&white), blue.alpha(), &white).hex(), "#0000FF80");
+        assert_eq!(RGBA::uncomposite(&white, 0.0, &white).hex(), "#00000000");
+        assert_eq!(RGBA::uncomposite(&RGB { r: 255, g: 0, b: 0 }, 1.0, &white).hex(), "#FF0000FF");
+    }
+
+    #[test]
+    fn premultiplies_rgba_colors() {
+        let color = RGBA { r: 200, g: 100, b: 50, a: 128 };
+        assert_eq!(color.premultiply().hex(), "#64321980");
+        assert_eq!(color.premultiply().unpremultiply().hex(), "#C7643280");
+        assert_eq!(RGBA { r: 200, g: 100, b: 50, a: 0 }.premultiply().unpremultiply().hex(), "#00000000");
+        assert_eq!(RGBA::from(&RGB { r: 1, g: 2, b: 3 }).hex(), "#010203FF");
+    }
+}
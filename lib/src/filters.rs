@@ -0,0 +1,112 @@
+use crate::{color::{LAB, HSL}, Pixels};
+
+/// Colors that can be excluded from the pixels before clustering
+pub enum Exclude {
+    /// Colors within a Delta E tolerance of the given color
+    Color(LAB, f32),
+    /// Colors with lightness below the given value (0 - 100)
+    Dark(f32),
+    /// Colors with lightness above the given value (0 - 100)
+    Light(f32),
+    /// Colors which look like human skin tones
+    Skin,
+}
+
+impl Exclude {
+    /**
+     * Checks if the color should be excluded
+     */
+    pub fn matches(&self, color: &LAB) -> bool {
+        match self {
+            Exclude::Color(target, tolerance) => target.distance(color) <= *tolerance,
+            Exclude::Dark(lightness) => color.l < *lightness,
+            Exclude::Light(lightness) => color.l > *lightness,
+            Exclude::Skin => is_skin_tone(color),
+        }
+    }
+}
+
+// Trapezoidal membership which is 1 between `lo` and `hi` and falls off linearly to 0 at the edges
+fn membership(val: f32, lo_edge: f32, lo: f32, hi: f32, hi_edge: f32) -> f32 {
+    if val < lo {
+        return ((val - lo_edge) / (lo - lo_edge)).max(0.0);
+    }
+    if val > hi {
+        return ((hi_edge - val) / (hi_edge - hi)).max(0.0);
+    }
+    return 1.0;
+}
+
+/// Calculates the likelihood (0 - 1) of a color being a human skin tone
+///
+/// Skin tones have warm hues between red and yellow in LAB with moderate chroma,
+/// and a moderate HSL saturation across the range of lightness of different skin types
+pub fn skin_tone(color: &LAB) -> f32 {
+    let hue = color.b.atan2(color.a).to_degrees();
+    let lab_likelihood = membership(hue, 15.0, 30.0, 65.0, 80.0)
+        * membership(color.chroma(), 4.0, 10.0, 40.0, 55.0)
+        * membership(color.l, 10.0, 25.0, 85.0, 95.0);
+
+    // Conversion to HSL is skipped for colors which are clearly not skin tones
+    if lab_likelihood == 0.0 {
+        return 0.0;
+    }
+
+    return lab_likelihood * membership(HSL::from(color).s, 0.05, 0.15, 0.7, 0.85);
+}
+
+/// Checks if a color falls in the range of common human skin tones
+pub fn is_skin_tone(color: &LAB) -> bool {
+    return skin_tone(color) >= 0.5;
+}
+
+/// Removes the pixels matching any of the exclusions
+pub fn exclude_pixels(pixels: Pixels, excludes: &[Exclude]) -> Pixels {
+    if excludes.is_empty() {
+        return pixels;
+    }
+
+    return pixels
+        .into_iter()
+        .filter(|color| !excludes.iter().any(|exclude| exclude.matches(color)))
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::RGB;
+
+    fn lab(r: u8, g: u8, b: u8) -> LAB {
+        return LAB::from(&RGB { r, g, b });
+    }
+
+    #[test]
+    fn detects_skin_tones() {
+        // Light to dark skin tones
+        for skin in [lab(233, 180, 150), lab(210, 160, 130), lab(176, 120, 90), lab(141, 85, 36), lab(90, 56, 37)].iter() {
+            assert!(is_skin_tone(skin), "{} is not a skin tone", RGB::from(skin).hex());
+        }
+        for color in [lab(255, 0, 0), lab(0, 128, 255), lab(128, 128, 128), lab(0, 200, 0), lab(255, 255, 255)].iter() {
+            assert_eq!(skin_tone(color), 0.0, "{} is a skin tone", RGB::from(color).hex());
+        }
+    }
+
+    #[test]
+    fn excludes_matching_pixels() {
+        let pixels = vec![lab(0, 0, 0), lab(255, 255, 255), lab(255, 0, 0), lab(250, 5, 5), lab(0, 0, 255), lab(176, 120, 90)];
+        let excludes = [
+            Exclude::Dark(10.0),
+            Exclude::Light(95.0),
+            Exclude::Color(lab(255, 0, 0), 5.0),
+            Exclude::Skin,
+        ];
+
+        let remaining = exclude_pixels(pixels.clone(), &excludes);
+        assert_eq!(remaining.len(), 1);
+        assert!(remaining[0] == lab(0, 0, 255));
+
+        assert_eq!(exclude_pixels(pixels.clone(), &[]).len(), pixels.len());
+        assert_eq!(exclude_pixels(pixels, &[Exclude::Color(lab(255, 0, 0), 0.0)]).len(), 5);
+    }
+}
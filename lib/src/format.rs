@@ -0,0 +1,224 @@
+use crate::color::{RGB, RGBA, LAB, HSL, LCH, HSV, HWB, CMYK, OKLAB, OKLCH, RGBSpace, WhitePoint};
+use std::str::FromStr;
+
+/// Syntax of the arguments in the CSS color functions
+#[derive(Clone, Copy, PartialEq)]
+pub enum Syntax {
+    /// Comma separated arguments like `rgb(255, 0, 0)` from CSS Color Level 3
+    Legacy,
+    /// Space separated arguments like `rgb(255 0 0)` from CSS Color Level 4
+    Modern,
+}
+
+impl FromStr for Syntax {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "legacy" => Ok(Syntax::Legacy),
+            "modern" => Ok(Syntax::Modern),
+            _ => Err(format!("Unknown CSS syntax '{}'", s)),
+        }
+    }
+}
+
+/// Options used while formatting colors as strings
+///
+/// Functions which only exist in CSS Color Level 4 like `hwb()`, `lab()`, `lch()`, `oklab()`, `oklch()` and `color()`
+/// always use the modern syntax.
+#[derive(Clone, Copy)]
+pub struct Format {
+    /// Maximum number of decimal places, trailing zeros are removed
+    pub precision: usize,
+    pub syntax: Syntax,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format {
+            precision: 2,
+            syntax: Syntax::Legacy,
+        }
+    }
+}
+
+impl Format {
+    /**
+     * Formats a number with the precision
+     */
+    pub fn number(&self, val: f32) -> String {
+        let mut num = format!("{:.*}", self.precision, val);
+        if num.contains('.') {
+            num = num.trim_end_matches('0').trim_end_matches('.').to_string();
+        }
+        // Rounding can leave a negative sign on zero
+        if num == "-0" {
+            num = String::from("0");
+        }
+        return num;
+    }
+
+    /**
+     * Formats a fraction (0 - 1) as a percentage with the precision
+     */
+    pub fn percent(&self, val: f32) -> String {
+        return format!("{}%", self.number(val * 100.0));
+    }
+
+    // Format with 2 more decimal places for values which are much smaller than LAB, like OKLab and fractions (0 - 1)
+    fn fine(&self) -> Format {
+        Format { precision: self.precision + 2, ..*self }
+    }
+
+    // Joins the arguments of a color function using the syntax
+    fn function(&self, name: &str, args: &[String], syntax: Syntax) -> String {
+        let separator = match syntax {
+            Syntax::Legacy => ", ",
+            Syntax::Modern => " ",
+        };
+        return format!("{}({})", name, args.join(separator));
+    }
+}
+
+/// Colors which can be formatted as a CSS color function
+pub trait ToCSS {
+    fn to_css(&self, format: &Format) -> String;
+}
+
+impl ToCSS for RGB {
+    fn to_css(&self, format: &Format) -> String {
+        let args = [self.r.to_string(), self.g.to_string(), self.b.to_string()];
+        return format.function("rgb", &args, format.syntax);
+    }
+}
+
+impl ToCSS for RGBA {
+    fn to_css(&self, format: &Format) -> String {
+        let alpha = format.fine().number(self.alpha());
+        let (r, g, b) = (self.r.to_string(), self.g.to_string(), self.b.to_string());
+
+        return match format.syntax {
+            Syntax::Legacy => format.function("rgba", &[r, g, b, alpha], Syntax::Legacy),
+            Syntax::Modern => format!("rgb({} {} {} / {})", r, g, b, alpha),
+        };
+    }
+}
+
+impl ToCSS for HSL {
+    fn to_css(&self, format: &Format) -> String {
+        let args = [format.number(self.h * 360.0), format.percent(self.s), format.percent(self.l)];
+        return format.function("hsl", &args, format.syntax);
+    }
+}
+
+// CSS defines `lab()` and `lch()` relative to D50, so the colors (relative to D65) are adapted before formatting
+impl ToCSS for LAB {
+    fn to_css(&self, format: &Format) -> String {
+        let d50 = self.adapt(WhitePoint::D65, WhitePoint::D50);
+        let args = [format.number(d50.l), format.number(d50.a), format.number(d50.b)];
+        return format.function("lab", &args, Syntax::Modern);
+    }
+}
+
+impl ToCSS for LCH {
+    fn to_css(&self, format: &Format) -> String {
+        let d50 = LCH::from(&LAB::from(self).adapt(WhitePoint::D65, WhitePoint::D50));
+        let args = [format.number(d50.l), format.number(d50.c), format.number(d50.h)];
+        return format.function("lch", &args, Syntax::Modern);
+    }
+}
+
+impl ToCSS for HWB {
+    fn to_css(&self, format: &Format) -> String {
+        let args = [format.number(self.h * 360.0), format.percent(self.w), format.percent(self.b)];
+        return format.function("hwb", &args, Syntax::Modern);
+    }
+}
+
+impl ToCSS for CMYK {
+    fn to_css(&self, format: &Format) -> String {
+        let args = [
+            format.percent(self.c),
+            format.percent(self.m),
+            format.percent(self.y),
+            format.percent(self.k),
+        ];
+        return format.function("device-cmyk", &args, format.syntax);
+    }
+}
+
+impl ToCSS for OKLAB {
+    fn to_css(&self, format: &Format) -> String {
+        let fine = format.fine();
+        let args = [fine.number(self.l), fine.number(self.a), fine.number(self.b)];
+        return format.function("oklab", &args, Syntax::Modern);
+    }
+}
+
+impl ToCSS for OKLCH {
+    fn to_css(&self, format: &Format) -> String {
+        let fine = format.fine();
+        let args = [fine.number(self.l), fine.number(self.c), format.number(self.h)];
+        return format.function("oklch", &args, Syntax::Modern);
+    }
+}
+
+/// Formats an HSV color like `hsv(174, 50%, 85.49%)`
+///
+/// HSV is not a CSS color space, so this notation is only meant to be read and is not valid CSS.
+pub fn hsv(color: &HSV, format: &Format) -> String {
+    let args = [format.number(color.h * 360.0), format.percent(color.s), format.percent(color.v)];
+    return format.function("hsv", &args, format.syntax);
+}
+
+/// Formats the values of a color separated by spaces like `53.24 80.09 67.2`, which is not valid CSS
+///
+/// This is useful for colors relative to another white point than the one CSS uses, like LAB relative to D65.
+pub fn values(values: &[f32], format: &Format) -> String {
+    return values.iter().map(|val| format.number(*val)).collect::<Vec<String>>().join(" ");
+}
+
+/// Formats a color as a CSS `color()` function in the given RGB working space like `color(display-p3 0.9175 0.2003 0.1386)`
+///
+/// Channels are calculated from the LAB color without rounding to 8-bit and are clipped to the gamut of the space.
+pub fn color_function(color: &LAB, space: RGBSpace, format: &Format) -> String {
+    let (r, g, b) = space.xyz_to_linear(color.to_xyz());
+    let fine = format.fine();
+    let channel = |val: f32| fine.number(space.from_linear(val).max(0.0).min(1.0));
+    let name = match space {
+        RGBSpace::SRGB => "srgb",
+        RGBSpace::AdobeRGB => "a98-rgb",
+        RGBSpace::DisplayP3 => "display-p3",
+        RGBSpace::Rec2020 => "rec2020",
+    };
+
+    return format!("color({} {} {} {})", name, channel(r), channel(g), channel(b));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn red() -> LAB {
+        return LAB::from(&RGB { r: 255, g: 0, b: 0 });
+    }
+
+    #[test]
+    fn formats_lab_and_lch_relative_to_d50_in_modern_syntax() {
+        let format = Format { precision: 1, syntax: Syntax::Legacy };
+        assert_eq!(red().to_css(&format), "lab(54.3 80.8 69.9)");
+        assert_eq!(LCH::from(&red()).to_css(&format), "lch(54.3 106.8 40.9)");
+    }
+
+    #[test]
+    fn formats_with_syntax_and_precision() {
+        let legacy = Format::default();
+        let modern = Format { precision: 0, syntax: Syntax::Modern };
+        assert_eq!(RGB { r: 255, g: 0, b: 0 }.to_css(&legacy), "rgb(255, 0, 0)");
+        assert_eq!(RGBA { r: 255, g: 0, b: 0, a: 128 }.to_css(&modern), "rgb(255 0 0 / 0.5)");
+        assert_eq!(HSL::from(&red()).to_css(&modern), "hsl(0 100% 50%)");
+        assert_eq!(CMYK::from(&red()).to_css(&legacy), "device-cmyk(0%, 100%, 100%, 0%)");
+        assert_eq!(legacy.number(-0.001), "0");
+        assert_eq!(color_function(&red(), RGBSpace::SRGB, &legacy), "color(srgb 1 0 0)");
+    }
+}
@@ -0,0 +1,174 @@
+use crate::color::{LAB, OKLAB, OKLCH, RGB, RGBSpace};
+
+/// Just noticeable difference (Delta E OK) between a color and its clipped version
+const JND: f32 = 0.02;
+/// Precision of the chroma while searching for the mapped color
+const MIN_CHROMA_STEP: f32 = 0.0001;
+/// Tolerance of the linear channels while checking the gamut, to ignore rounding errors of the conversions
+const TOLERANCE: f32 = 0.0001;
+
+// Checks if linear RGB channels are within the gamut
+fn is_inside(linear: (f32, f32, f32)) -> bool {
+    let inside = |val: f32| val >= -TOLERANCE && val <= 1.0 + TOLERANCE;
+    return inside(linear.0) && inside(linear.1) && inside(linear.2);
+}
+
+// Clips XYZ color (0 - 100) to the gamut of the space and returns the encoded channels (0 - 1)
+fn clip_encoded(xyz: (f32, f32, f32), space: RGBSpace) -> (f32, f32, f32) {
+    let (r, g, b) = space.xyz_to_linear(xyz);
+    let encode = |val: f32| space.from_linear(val.max(0.0).min(1.0));
+    return (encode(r), encode(g), encode(b));
+}
+
+// Converts encoded channels (0 - 1) of the space to an OKLab color
+fn encoded_to_oklab(rgb: (f32, f32, f32), space: RGBSpace) -> OKLAB {
+    let linear = (space.to_linear(rgb.0), space.to_linear(rgb.1), space.to_linear(rgb.2));
+    return OKLAB::from_xyz(space.linear_to_xyz(linear));
+}
+
+// Converts encoded channels (0 - 1) to 8-bit RGB
+fn encoded_to_rgb(rgb: (f32, f32, f32)) -> RGB {
+    let to_u8 = |val: f32| (val * 255.0).round() as u8;
+    return RGB { r: to_u8(rgb.0), g: to_u8(rgb.1), b: to_u8(rgb.2) };
+}
+
+/// Checks if the LAB color (relative to D65) can be shown in the RGB working space
+pub fn in_gamut(color: &LAB, space: RGBSpace) -> bool {
+    return is_inside(space.xyz_to_linear(color.to_xyz()));
+}
+
+/// Converts the LAB color (relative to D65) to the RGB working space by clipping the channels
+///
+/// The returned flag is `true` when the color was out of gamut and had to be clipped.
+/// Clipping is fast but can shift the hue and lightness of colors that are far outside the gamut.
+pub fn clip(color: &LAB, space: RGBSpace) -> (RGB, bool) {
+    let xyz = color.to_xyz();
+    return (encoded_to_rgb(clip_encoded(xyz, space)), !is_inside(space.xyz_to_linear(xyz)));
+}
+
+/// Maps the LAB color (relative to D65) into the gamut of the RGB working space
+///
+/// This follows the gamut mapping algorithm of CSS Color Level 4. The chroma of the color is reduced
+/// in OKLCH, keeping its lightness and hue, until clipping it makes a difference below the just noticeable
+/// difference. The returned flag is `true` when the color was out of gamut and had to be mapped.
+pub fn map_to_gamut(color: &LAB, space: RGBSpace) -> (RGB, bool) {
+    let xyz = color.to_xyz();
+    if is_inside(space.xyz_to_linear(xyz)) {
+        return (RGB::from_xyz_in(xyz, space), false);
+    }
+
+    let origin = OKLCH::from(&OKLAB::from_xyz(xyz));
+    if origin.l.is_nan() || origin.c.is_nan() {
+        return (RGB { r: 0, g: 0, b: 0 }, true);
+    }
+    if origin.l >= 1.0 {
+        return (RGB { r: 255, g: 255, b: 255 }, true);
+    }
+    if origin.l <= 0.0 {
+        return (RGB { r: 0, g: 0, b: 0 }, true);
+    }
+
+    let xyz_of = |chroma: f32| OKLAB::from(&OKLCH { l: origin.l, c: chroma, h: origin.h }).to_xyz();
+
+    let mut current = xyz;
+    let mut clipped = clip_encoded(current, space);
+    if encoded_to_oklab(clipped, space).distance(&OKLAB::from_xyz(current)) < JND {
+        return (encoded_to_rgb(clipped), true);
+    }
+
+    // Binary search for the highest chroma whose clipped color is not noticeably different
+    let (mut min, mut max) = (0.0, origin.c);
+    let mut min_in_gamut = true;
+    while max - min > MIN_CHROMA_STEP {
+        let chroma = (min + max) / 2.0;
+        current = xyz_of(chroma);
+
+        if min_in_gamut && is_inside(space.xyz_to_linear(current)) {
+            min = chroma;
+            continue;
+        }
+
+        clipped = clip_encoded(current, space);
+        let diff = encoded_to_oklab(clipped, space).distance(&OKLAB::from_xyz(current));
+        if diff < JND {
+            if JND - diff < MIN_CHROMA_STEP {
+                return (encoded_to_rgb(clipped), true);
+            }
+            min_in_gamut = false;
+            min = chroma;
+        } else {
+            max = chroma;
+        }
+    }
+
+    return (encoded_to_rgb(clip_encoded(current, space)), true);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // LAB color of the linear channels (0 - 1) of the space
+    fn lab_of(linear: (f32, f32, f32), space: RGBSpace) -> LAB {
+        return LAB::from_xyz(space.linear_to_xyz(linear));
+    }
+
+    #[test]
+    fn checks_gamut_of_spaces() {
+        let srgb_red = LAB::from_rgb(255, 0, 0);
+        assert!(in_gamut(&srgb_red, RGBSpace::SRGB));
+        assert!(in_gamut(&srgb_red, RGBSpace::DisplayP3));
+        assert!(in_gamut(&srgb_red, RGBSpace::Rec2020));
+        assert!(in_gamut(&LAB::from_rgb(255, 255, 255), RGBSpace::SRGB));
+        assert!(in_gamut(&LAB::from_rgb(0, 0, 0), RGBSpace::SRGB));
+
+        let p3_green = lab_of((0.0, 1.0, 0.0), RGBSpace::DisplayP3);
+        assert!(!in_gamut(&p3_green, RGBSpace::SRGB));
+        assert!(in_gamut(&p3_green, RGBSpace::DisplayP3));
+        assert!(in_gamut(&p3_green, RGBSpace::Rec2020));
+        assert!(!in_gamut(&lab_of((0.0, 1.0, 0.0), RGBSpace::Rec2020), RGBSpace::DisplayP3));
+    }
+
+    #[test]
+    fn keeps_colors_inside_the_gamut() {
+        for rgb in [RGB { r: 255, g: 0, b: 0 }, RGB { r: 18, g: 52, b: 86 }, RGB { r: 255, g: 255, b: 255 }].iter() {
+            let (mapped, was_mapped) = map_to_gamut(&LAB::from(rgb), RGBSpace::SRGB);
+            assert_eq!(mapped.hex(), rgb.hex());
+            assert!(!was_mapped);
+            assert!(!clip(&LAB::from(rgb), RGBSpace::SRGB).1);
+        }
+    }
+
+    #[test]
+    fn maps_colors_keeping_lightness_and_hue() {
+        let colors = [
+            lab_of((0.0, 1.0, 0.0), RGBSpace::DisplayP3),
+            lab_of((1.0, 0.0, 0.0), RGBSpace::Rec2020),
+            lab_of((0.0, 0.0, 1.0), RGBSpace::Rec2020),
+            LAB { l: 70.0, a: -20.0, b: -90.0 },
+            LAB { l: 40.0, a: 100.0, b: 20.0 },
+        ];
+
+        for color in colors.iter() {
+            assert!(!in_gamut(color, RGBSpace::SRGB));
+            assert!(clip(color, RGBSpace::SRGB).1);
+
+            let (mapped, was_mapped) = map_to_gamut(color, RGBSpace::SRGB);
+            assert!(was_mapped);
+
+            // Only the chroma is reduced, apart from the just noticeable difference of the final clip
+            // and the rounding to 8-bit
+            let (origin, result) = (OKLCH::from(color), OKLCH::from(&mapped));
+            let reduced = OKLAB::from(&OKLCH { c: result.c, ..origin.clone() });
+            let diff = reduced.distance(&OKLAB::from(&mapped));
+            assert!(diff < JND + 0.005, "{} is {} away from the lightness and hue", mapped.hex(), diff);
+            assert!(result.c < origin.c);
+        }
+    }
+
+    #[test]
+    fn maps_lightness_outside_the_range() {
+        assert_eq!(map_to_gamut(&LAB { l: 110.0, a: 10.0, b: 0.0 }, RGBSpace::SRGB).0.hex(), "#FFFFFF");
+        assert_eq!(map_to_gamut(&LAB { l: -5.0, a: 10.0, b: 0.0 }, RGBSpace::SRGB).0.hex(), "#000000");
+    }
+}
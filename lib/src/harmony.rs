@@ -0,0 +1,209 @@
+use crate::color::{LAB, LCH, OKLCH};
+use std::{fmt, str::FromStr};
+
+/// Color harmonies made by rotating the hue of a base color
+#[derive(Clone, Copy, PartialEq)]
+pub enum Harmony {
+    /// Opposite hue (180 degrees)
+    Complementary,
+    /// Hues next to the opposite hue (150 and 210 degrees)
+    SplitComplementary,
+    /// Hues evenly spaced in thirds (120 and 240 degrees)
+    Triadic,
+    /// Two pairs of complementary hues forming a rectangle (60, 180 and 240 degrees)
+    Tetradic,
+    /// Neighbouring hues (-30 and 30 degrees)
+    Analogous,
+}
+
+impl Harmony {
+    /// All the harmonies which can be generated
+    pub const ALL: [Harmony; 5] = [
+        Harmony::Complementary,
+        Harmony::SplitComplementary,
+        Harmony::Triadic,
+        Harmony::Tetradic,
+        Harmony::Analogous,
+    ];
+
+    /**
+     * Hue offsets in degrees of the companion colors from the base color
+     */
+    pub fn offsets(&self) -> &'static [f32] {
+        match self {
+            Harmony::Complementary => &[180.0],
+            Harmony::SplitComplementary => &[150.0, 210.0],
+            Harmony::Triadic => &[120.0, 240.0],
+            Harmony::Tetradic => &[60.0, 180.0, 240.0],
+            Harmony::Analogous => &[-30.0, 30.0],
+        }
+    }
+
+    /**
+     * Generates the harmony of the base color by rotating its hue in OKLCH
+     *
+     * The base color is the first color, followed by its companions
+     */
+    pub fn generate(&self, base: &LAB) -> Vec<LAB> {
+        return self.generate_in(base, HueSpace::OKLCH);
+    }
+
+    /**
+     * Generates the harmony of the base color by rotating its hue in the given space
+     *
+     * The lightness and chroma of the base color are kept, so companions outside the gamut
+     * are mapped into it when converted to RGB.
+     */
+    pub fn generate_in(&self, base: &LAB, space: HueSpace) -> Vec<LAB> {
+        let rotate = |hue: f32, offset: f32| (hue + offset).rem_euclid(360.0);
+        let mut colors = vec![base.clone()];
+
+        match space {
+            HueSpace::LCH => {
+                let lch = LCH::from(base);
+                colors.extend(self.offsets().iter().map(|offset| {
+                    LAB::from(&LCH { h: rotate(lch.h, *offset), ..lch.clone() })
+                }));
+            },
+            HueSpace::OKLCH => {
+                let oklch = OKLCH::from(base);
+                colors.extend(self.offsets().iter().map(|offset| {
+                    LAB::from(&OKLCH { h: rotate(oklch.h, *offset), ..oklch.clone() })
+                }));
+            },
+        }
+
+        return colors;
+    }
+}
+
+impl FromStr for Harmony {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "complementary" => Ok(Harmony::Complementary),
+            "split-complementary" => Ok(Harmony::SplitComplementary),
+            "triadic" => Ok(Harmony::Triadic),
+            "tetradic" => Ok(Harmony::Tetradic),
+            "analogous" => Ok(Harmony::Analogous),
+            _ => Err(format!("Unknown harmony '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for Harmony {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Harmony::Complementary => "Complementary",
+            Harmony::SplitComplementary => "Split Complementary",
+            Harmony::Triadic => "Triadic",
+            Harmony::Tetradic => "Tetradic",
+            Harmony::Analogous => "Analogous",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Polar color spaces in which the hue is rotated
+#[derive(Clone, Copy, PartialEq)]
+pub enum HueSpace {
+    /// LCh, the polar form of CIELAB
+    LCH,
+    /// OKLCH, the polar form of OKLab which keeps the perceived hue more uniform
+    OKLCH,
+}
+
+impl Default for HueSpace {
+    fn default() -> Self {
+        HueSpace::OKLCH
+    }
+}
+
+impl FromStr for HueSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "lch" => Ok(HueSpace::LCH),
+            "oklch" => Ok(HueSpace::OKLCH),
+            _ => Err(format!("Unknown hue space '{}'", s)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::RGB;
+
+    fn lab(r: u8, g: u8, b: u8) -> LAB {
+        return LAB::from(&RGB { r, g, b });
+    }
+
+    // Signed difference between two hues in degrees, from -180 to 180
+    fn hue_diff(from: f32, to: f32) -> f32 {
+        return (to - from + 180.0).rem_euclid(360.0) - 180.0;
+    }
+
+    #[test]
+    fn rotates_hue_by_offsets_in_oklch() {
+        let bases = [lab(224, 72, 57), lab(40, 120, 200), lab(250, 220, 90)];
+
+        for harmony in Harmony::ALL.iter() {
+            for base in bases.iter() {
+                let colors = harmony.generate(base);
+                assert_eq!(colors.len(), 1 + harmony.offsets().len());
+                assert!(colors[0] == *base);
+
+                let base = OKLCH::from(base);
+                for (color, offset) in colors[1..].iter().zip(harmony.offsets()) {
+                    let color = OKLCH::from(color);
+                    assert!((color.l - base.l).abs() < 1e-3);
+                    assert!((color.c - base.c).abs() < 1e-3);
+                    assert!(hue_diff(base.h + offset, color.h).abs() < 0.1, "{} rotated by {} is {}", base.h, offset, color.h);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rotates_hue_by_offsets_in_lch() {
+        let base = lab(224, 72, 57);
+        let lch = LCH::from(&base);
+
+        for harmony in Harmony::ALL.iter() {
+            let colors = harmony.generate_in(&base, HueSpace::LCH);
+            assert_eq!(colors.len(), 1 + harmony.offsets().len());
+            assert!(colors[0] == base);
+
+            for (color, offset) in colors[1..].iter().zip(harmony.offsets()) {
+                let color = LCH::from(color);
+                assert!((color.l - lch.l).abs() < 1e-2);
+                assert!((color.c - lch.c).abs() < 1e-2);
+                assert!(hue_diff(lch.h + offset, color.h).abs() < 0.1, "{} rotated by {} is {}", lch.h, offset, color.h);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_hue_within_a_turn() {
+        // Analogous harmony crosses 0 degrees from a hue close to it
+        let base = LAB::from(&OKLCH { l: 0.6, c: 0.1, h: 10.0 });
+        let colors = Harmony::Analogous.generate(&base);
+
+        let hues: Vec<f32> = colors.iter().map(|color| OKLCH::from(color).h).collect();
+        assert!(hues.iter().all(|h| *h >= 0.0 && *h < 360.0));
+        assert!((hues[1] - 340.0).abs() < 0.1);
+        assert!((hues[2] - 40.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn offsets_of_harmonies() {
+        assert_eq!(Harmony::Complementary.offsets(), &[180.0]);
+        assert_eq!(Harmony::SplitComplementary.offsets(), &[150.0, 210.0]);
+        assert_eq!(Harmony::Triadic.offsets(), &[120.0, 240.0]);
+        assert_eq!(Harmony::Tetradic.offsets(), &[60.0, 180.0, 240.0]);
+        assert_eq!(Harmony::Analogous.offsets(), &[-30.0, 30.0]);
+    }
+}
@@ -1,17 +1,30 @@
+pub mod accessibility;
+pub mod background;
 pub mod color;
+pub mod filters;
+pub mod format;
+pub mod gamut;
+pub mod harmony;
+pub mod palette;
+pub mod parse;
+pub mod swatches;
 pub mod weights;
 
+use std::str::FromStr;
 use rand::{distributions::WeightedIndex, prelude::*};
-use color::LAB;
-use weights::WeightFn;
+use color::{LAB, OKLAB, RGB, RGBA, RGBSpace};
+use weights::Weight;
 
 #[cfg(target_arch = "wasm32")]
 use {
     wasm_bindgen::{prelude::*, JsCast},
     web_sys::{CanvasRenderingContext2d, HtmlCanvasElement},
-    weights::{Mood, resolve_mood},
-    color::{RGB, HSL},
-    serde_derive::Serialize,
+    weights::{Mood, BlendMode, BoxedWeight, resolve_mood, parse_blend},
+    palette::{Palette, SortOrder},
+    accessibility::WCAGLevel,
+    color::{HSL, HSV, HWB, LCH},
+    gamut::in_gamut,
+    serde::Serialize,
 };
 
 #[cfg(not(target_arch = "wasm32"))]
@@ -20,50 +33,233 @@ use {
     crossbeam_utils::thread,
 };
 
+#[cfg(all(target_arch = "wasm32", not(feature = "serde")))]
+compile_error!("The `serde` feature is required to build for WebAssembly, keep the default features enabled");
+
 pub type Pixels = Vec<LAB>;
 
-/// Recalculates the means using a weight function
-fn recal_means(colors: &Vec<LAB>, weight: WeightFn) -> LAB {
-    let mut new_color = LAB {
-        l: 0.0,
-        a: 0.0,
-        b: 0.0
-    };
+/// Converts raw RGBA pixel data into a grid of colors while respecting the alpha channel
+///
+/// Fully transparent pixels are `None` and partially transparent pixels are
+/// composited over the `background` color
+pub fn grid_from_rgba(data: &[u8], background: &RGB) -> Vec<Option<LAB>> {
+    return data
+        .chunks_exact(4)
+        .map(|pix| {
+            if pix[3] == 0 {
+                return None;
+            }
+
+            let color = RGBA { r: pix[0], g: pix[1], b: pix[2], a: pix[3] };
+            Some(LAB::from(&color.composite(background)))
+        })
+        .collect();
+}
+
+// Converts RGBA channels normalized to 0 - 1 into a grid of colors after compositing them over the
+// background in the domain of `to_lab`, which is linear or encoded sRGB
+fn grid_from_channels<T: Copy>(
+    data: &[T],
+    normalize: impl Fn(T) -> f32,
+    background: (f32, f32, f32),
+    to_lab: impl Fn((f32, f32, f32)) -> LAB,
+) -> Vec<Option<LAB>> {
+    return data
+        .chunks_exact(4)
+        .map(|pix| {
+            let a = normalize(pix[3]).min(1.0);
+            if a <= 0.0 {
+                return None;
+            }
+
+            let mix = |fg: T, bg: f32| normalize(fg).max(0.0) * a + bg * (1.0 - a);
+            Some(to_lab((mix(pix[0], background.0), mix(pix[1], background.1), mix(pix[2], background.2))))
+        })
+        .collect();
+}
+
+/// Converts raw 16-bit RGBA pixel data into a grid of colors at full precision
+///
+/// Fully transparent pixels are `None` and partially transparent pixels are
+/// composited over the `background` color
+pub fn grid_from_rgba16(data: &[u16], background: &RGB) -> Vec<Option<LAB>> {
+    let bg = (background.r as f32 / 255.0, background.g as f32 / 255.0, background.b as f32 / 255.0);
+    return grid_from_channels(data, |val| val as f32 / 65535.0, bg, |rgb| LAB::from_encoded(rgb, RGBSpace::SRGB));
+}
+
+/// Converts raw floating point RGBA pixel data into a grid of colors at full precision
+///
+/// Channels are encoded sRGB from 0 to 1, or linear sRGB when `linear` is set, which can go above 1
+/// for HDR images. Fully transparent pixels are `None` and partially transparent pixels are
+/// composited over the `background` color.
+pub fn grid_from_rgba32f(data: &[f32], background: &RGB, linear: bool) -> Vec<Option<LAB>> {
+    let space = RGBSpace::SRGB;
+    let mut bg = (background.r as f32 / 255.0, background.g as f32 / 255.0, background.b as f32 / 255.0);
+
+    if linear {
+        bg = (space.to_linear(bg.0), space.to_linear(bg.1), space.to_linear(bg.2));
+        return grid_from_channels(data, |val| val, bg, |rgb| LAB::from_linear(rgb, space));
+    }
+    return grid_from_channels(data, |val| val, bg, |rgb| LAB::from_encoded(rgb, space));
+}
+
+/// Converts raw RGBA pixel data into `Pixels` while respecting the alpha channel
+///
+/// Fully transparent pixels are skipped and partially transparent pixels are
+/// composited over the `background` color
+pub fn pixels_from_rgba(data: &[u8], background: &RGB) -> Pixels {
+    return grid_from_rgba(data, background).into_iter().flatten().collect();
+}
+
+/// Converts raw RGBA pixel data into colors with their alpha channel
+pub fn colors_from_rgba(data: &[u8]) -> Vec<RGBA> {
+    return data
+        .chunks_exact(4)
+        .map(|pix| RGBA { r: pix[0], g: pix[1], b: pix[2], a: pix[3] })
+        .collect();
+}
+
+/// Calculates the alpha of the palette colors from the pixels they were created from
+///
+/// Every visible pixel is assigned to its nearest color in the palette, and the average alpha
+/// of these pixels is used to recover the straight color from the palette color which was composited
+/// over the `background`. Palette colors without any pixels are opaque.
+pub fn palette_alpha(palette: &[(LAB, f32)], pixels: &[RGBA], background: &RGB) -> Vec<RGBA> {
+    let colors: Vec<LAB> = palette.iter().map(|(color, _)| color.clone()).collect();
+    let mut alphas: Vec<(f32, usize)> = vec![(0.0, 0); colors.len()];
+
+    if !colors.is_empty() {
+        for pixel in pixels.iter().filter(|pixel| pixel.a != 0) {
+            let (i, _) = LAB::from(&pixel.composite(background)).nearest(&colors);
+            alphas[i].0 += pixel.alpha();
+            alphas[i].1 += 1;
+        }
+    }
+
+    return colors
+        .iter()
+        .zip(alphas.iter())
+        .map(|(color, &(sum, count))| {
+            let alpha = if count == 0 { 1.0 } else { sum / count as f32 };
+            RGBA::uncomposite(&RGB::from(color), alpha, background)
+        })
+        .collect();
+}
+
+/// Color spaces in which the pixels can be clustered
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum ClusterSpace {
+    /// CIELAB with Delta E(1994) as the distance
+    CIELAB,
+    /// OKLab with Euclidean distance, which is more perceptually uniform for hue
+    OKLab,
+}
+
+impl FromStr for ClusterSpace {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "cielab" | "lab" => Ok(ClusterSpace::CIELAB),
+            "oklab" => Ok(ClusterSpace::OKLab),
+            _ => Err(format!("Unknown color space '{}'", s)),
+        }
+    }
+}
+
+/// Colors which can be clustered
+trait Cluster: Clone + Send + Sync {
+    /// Distance between two colors
+    fn dist(&self, other: &Self) -> f32;
+    /// Channels of the color used to calculate the means
+    fn channels(&self) -> [f32; 3];
+    fn from_channels(channels: [f32; 3]) -> Self;
+}
+
+impl Cluster for LAB {
+    fn dist(&self, other: &Self) -> f32 {
+        self.distance(other)
+    }
+
+    fn channels(&self) -> [f32; 3] {
+        [self.l, self.a, self.b]
+    }
+
+    fn from_channels(channels: [f32; 3]) -> Self {
+        LAB { l: channels[0], a: channels[1], b: channels[2] }
+    }
+}
+
+impl Cluster for OKLAB {
+    fn dist(&self, other: &Self) -> f32 {
+        self.distance(other)
+    }
+
+    fn channels(&self) -> [f32; 3] {
+        [self.l, self.a, self.b]
+    }
+
+    fn from_channels(channels: [f32; 3]) -> Self {
+        OKLAB { l: channels[0], a: channels[1], b: channels[2] }
+    }
+}
+
+/// Finds the index and distance from nearest mean
+fn nearest<T: Cluster>(color: &T, means: &[T]) -> (usize, f32) {
+    return means
+        .iter()
+        .map(|c| color.dist(c))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.partial_cmp(&b).expect("NaN encountered"))
+        .unwrap();
+}
+
+/// Recalculates the mean of a cluster using the weights of the colors
+///
+/// The previous mean is kept if the cluster has no weight
+fn recal_means<T: Cluster>(points: &[T], weights: &[f32], cluster: &[usize], mean: &T) -> T {
+    let mut channels = [0.0; 3];
     let mut w_sum = 0.0;
 
-    for col in colors.iter() {
-        let w = weight(col);
+    for &i in cluster.iter() {
+        let w = weights[i];
+        let color = points[i].channels();
         w_sum += w;
-        new_color.l += w * col.l;
-        new_color.a += w * col.a;
-        new_color.b += w * col.b;
+        for c in 0..3 {
+            channels[c] += w * color[c];
+        }
+    }
+
+    if w_sum <= 0.0 {
+        return mean.clone();
     }
 
-    new_color.l /= w_sum;
-    new_color.a /= w_sum;
-    new_color.b /= w_sum;
+    for c in channels.iter_mut() {
+        *c /= w_sum;
+    }
 
-    return new_color;
+    return T::from_channels(channels);
 }
 
 #[cfg(target_arch = "wasm32")]
-fn find_clusters(pixels: &Pixels, means: &Pixels, k: usize) -> Vec<Pixels> {
-    let mut clusters: Vec<Pixels> = vec![Vec::new(); k];
+fn find_clusters<T: Cluster>(points: &[T], means: &[T], k: usize) -> Vec<Vec<usize>> {
+    let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); k];
 
-    for color in pixels.iter() {
-        clusters[color.nearest(means).0].push(color.clone());
+    for (i, color) in points.iter().enumerate() {
+        clusters[nearest(color, means).0].push(i);
     }
 
     return clusters;
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-fn find_clusters(pixels: &Pixels, means: &Pixels, k: usize) -> Vec<Pixels> {
+fn find_clusters<T: Cluster>(points: &[T], means: &[T], k: usize) -> Vec<Vec<usize>> {
     const NUM_THREADS: usize = 5;
 
-    let num_pixels = pixels.len();
+    let num_pixels = points.len();
     let sample_size = num_pixels / NUM_THREADS;
-    let mut clusters: Vec<Pixels> = vec![Vec::new(); k as usize];
+    let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); k];
     
     thread::scope(|s| {
         let mut threads = vec![];
@@ -71,15 +267,19 @@ fn find_clusters(pixels: &Pixels, means: &Pixels, k: usize) -> Vec<Pixels> {
         // Data parallelism where each thread operates on a sample of data
         for i in 0..NUM_THREADS {
             let start = i * sample_size;
-            let end = cmp::min(start + sample_size, num_pixels);
+            // Last thread also takes the remaining pixels
+            let end = if i == NUM_THREADS - 1 {
+                num_pixels
+            } else {
+                cmp::min(start + sample_size, num_pixels)
+            };
 
             // Each thread is responsible in finding the nearest cluster mean for each point in the sample (map phase)
             threads.push(
                 s.spawn(move |_| {
-                    let mut clusters: Vec<Vec<LAB>> = vec![Vec::new(); k];
+                    let mut clusters: Vec<Vec<usize>> = vec![Vec::new(); k];
                     for pixel_idx in start..end {
-                        let color = &pixels[pixel_idx];
-                        clusters[color.nearest(&means).0].push(color.clone());
+                        clusters[nearest(&points[pixel_idx], means).0].push(pixel_idx);
                     }
                     return clusters;
                 })
@@ -99,25 +299,24 @@ fn find_clusters(pixels: &Pixels, means: &Pixels, k: usize) -> Vec<Pixels> {
     return clusters;
 }
 
-/// Parallelized K-means++ clustering to create the palette from pixels
-pub fn pigments_pixels(pixels: &Pixels, k: u8, weight: WeightFn, max_iter: Option<u16>) -> Vec<(LAB, f32)> {
+/// K-means++ clustering of the colors using their weights
+fn kmeans<T: Cluster>(points: &[T], weights: &[f32], k: usize, max_iter: Option<u16>) -> Vec<(T, f32)> {
     // Values referenced from https://scikit-learn.org/stable/modules/generated/sklearn.cluster.KMeans.html
     const TOLERANCE: f32 = 1e-4;
     const MAX_ITER: u16 = 300;
 
     let mut rng = rand::thread_rng();
-    let k = k as usize;
 
     // Randomly pick the starting cluster center
-    let i: usize = rng.gen_range(0, pixels.len());
-    let mut means: Pixels = vec![pixels[i].clone()];
+    let i: usize = rng.gen_range(0, points.len());
+    let mut means: Vec<T> = vec![points[i].clone()];
 
     // Pick the remaining (k-1) means
     for _ in 0..(k - 1) {
         // Calculate the (nearest_distance)^2 for every color in the image
-        let distances: Vec<f32> = pixels
+        let distances: Vec<f32> = points
             .iter()
-            .map(|color| (color.nearest(&means).1).powi(2))
+            .map(|color| (nearest(color, &means).1).powi(2))
             .collect();
 
         // Create a weighted distribution based on distance^2
@@ -126,11 +325,11 @@ pub fn pigments_pixels(pixels: &Pixels, k: u8, weight: WeightFn, max_iter: Optio
             Ok(t) => t,
             Err(_) => {
                 // Calculate the dominance of each color
-                let mut palette: Vec<(LAB, f32)> = means.iter().map(|c| (c.clone(), 0.0)).collect();
+                let mut palette: Vec<(T, f32)> = means.iter().map(|c| (c.clone(), 0.0)).collect();
 
-                let len = pixels.len() as f32;
-                for color in pixels.iter() {
-                    let near = color.nearest(&means).0;
+                let len = points.len() as f32;
+                for color in points.iter() {
+                    let near = nearest(color, &means).0;
                     palette[near].1 += 1.0 / len;
                 }
 
@@ -139,21 +338,21 @@ pub fn pigments_pixels(pixels: &Pixels, k: u8, weight: WeightFn, max_iter: Optio
         };
 
         // Using the distances^2 as weights, pick a color and use it as a cluster center
-        means.push(pixels[dist.sample(&mut rng)].clone());
+        means.push(points[dist.sample(&mut rng)].clone());
     }
 
-    let mut clusters: Vec<Vec<LAB>>;
+    let mut clusters: Vec<Vec<usize>>;
     let mut iters_left = max_iter.unwrap_or(MAX_ITER);
 
     loop {
         // Assignment step: Clusters are formed in current iteration
-        clusters = find_clusters(pixels, &means, k);
+        clusters = find_clusters(points, &means, k);
 
         // Updation step: New cluster means are calculated
         let mut changed: bool = false;
         for i in 0..clusters.len() {
-            let new_mean = recal_means(&clusters[i], weight);
-            if means[i].distance(&new_mean) > TOLERANCE {
+            let new_mean = recal_means(points, weights, &clusters[i], &means[i]);
+            if means[i].dist(&new_mean) > TOLERANCE {
                 changed = true;
             }
 
@@ -175,23 +374,77 @@ pub fn pigments_pixels(pixels: &Pixels, k: u8, weight: WeightFn, max_iter: Optio
         .map(|(i, cluster)| {
             (
                 means[i].clone(),
-                cluster.len() as f32 / pixels.len() as f32,
+                cluster.len() as f32 / points.len() as f32,
             )
         })
         .collect();
 }
 
+/// Parallelized K-means++ clustering to create the palette from pixels
+///
+/// The weight can be a closure, a reference to a weight or a boxed weight as returned by `resolve_mood`
+pub fn pigments_pixels(pixels: &Pixels, k: u8, weight: impl Weight, max_iter: Option<u16>) -> Vec<(LAB, f32)> {
+    return pigments_pixels_in(pixels, k, weight, max_iter, ClusterSpace::CIELAB);
+}
+
+/// Parallelized K-means++ clustering to create the palette from pixels in the given color space
+pub fn pigments_pixels_in(
+    pixels: &Pixels,
+    k: u8,
+    weight: impl Weight,
+    max_iter: Option<u16>,
+    space: ClusterSpace,
+) -> Vec<(LAB, f32)> {
+    // Nothing to cluster when every pixel was skipped or no colors are asked for
+    if pixels.is_empty() || k == 0 {
+        return Vec::new();
+    }
+
+    // Weights only depend on the colors, so they are calculated once
+    let weights: Vec<f32> = pixels.iter().map(|color| weight.weight(color)).collect();
+
+    return match space {
+        ClusterSpace::CIELAB => kmeans(pixels, &weights, k as usize, max_iter),
+        ClusterSpace::OKLab => {
+            let points: Vec<OKLAB> = pixels.iter().map(OKLAB::from).collect();
+            kmeans(&points, &weights, k as usize, max_iter)
+                .iter()
+                .map(|(color, dominance)| (LAB::from(color), *dominance))
+                .collect()
+        },
+    };
+}
+
 
 #[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
-pub fn pigments(canvas: HtmlCanvasElement, k: u8, mood: Mood, batch_size: Option<u32>) -> JsValue {
+pub fn pigments(
+    canvas: HtmlCanvasElement,
+    k: u8,
+    mood: Mood,
+    batch_size: Option<u32>,
+    background: Option<String>,
+    blend: Option<String>,
+    blend_mode: Option<BlendMode>,
+    cluster_space: Option<ClusterSpace>,
+    sort: Option<SortOrder>,
+    text_level: Option<WCAGLevel>,
+) -> JsValue {
 
     #[derive(Serialize)]
     struct PaletteColor {
         pub dominance: f32,
         pub hex: String,
         pub rgb: RGB,
+        pub rgba: RGBA,
         pub hsl: HSL,
+        pub hsv: HSV,
+        pub hwb: HWB,
+        pub lch: LCH,
+        pub role: Option<String>,
+        pub clipped: bool,
+        pub text: String,
+        pub text_contrast: f32,
     }
 
     // Get context from canvas element
@@ -208,15 +461,22 @@ pub fn pigments(canvas: HtmlCanvasElement, k: u8, mood: Mood, batch_size: Option
         .unwrap()
         .data();
 
+    // Transparent pixels are composited over the background, white by default
+    let background = match background {
+        Some(color) => match color.parse::<RGB>() {
+            Ok(rgb) => rgb,
+            Err(e) => wasm_bindgen::throw_str(&e.to_string()),
+        },
+        None => RGB { r: 255, g: 255, b: 255 },
+    };
+
     // Convert to Pixels type
-    let mut pixels: Pixels = (0..data.len())
-        .step_by(4)
-        .map(|i| LAB::from_rgb(data[i], data[i+1], data[i+2]))
-        .collect();
+    let colors = colors_from_rgba(&data);
+    let mut pixels: Pixels = pixels_from_rgba(&data, &background);
 
     // Randomly choose a sample of batch size if given
     let batch = batch_size.unwrap_or(0);
-    if batch != 0 && batch < canvas.width() * canvas.height() && batch > k.into() {
+    if batch != 0 && (batch as usize) < pixels.len() && batch > k.into() {
         let mut rng = rand::thread_rng();
         pixels = pixels
             .choose_multiple(&mut rng, batch as usize)
@@ -225,16 +485,39 @@ pub fn pigments(canvas: HtmlCanvasElement, k: u8, mood: Mood, batch_size: Option
     }
     
     // Generate the color palette and store it in a Vector of PaletteColor
-    let weight: WeightFn = resolve_mood(&mood);
-    let palettes: Vec<PaletteColor> = pigments_pixels(&pixels, k, weight, None)
+    // Blend of moods takes precedence over the single mood
+    let weight: BoxedWeight = match blend {
+        Some(moods) => match parse_blend(&moods, blend_mode.unwrap_or(BlendMode::Linear)) {
+            Ok(weight) => weight,
+            Err(e) => wasm_bindgen::throw_str(&e),
+        },
+        None => resolve_mood(&mood),
+    };
+    let space = cluster_space.unwrap_or(ClusterSpace::CIELAB);
+    let mut palette = Palette::new(pigments_pixels_in(&pixels, k, weight.as_ref(), None, space));
+    palette.sort(sort.unwrap_or(SortOrder::Dominance));
+    let alphas = palette_alpha(&palette.colors(), &colors, &background);
+    let texts = palette.text_colors(text_level.unwrap_or(WCAGLevel::AA));
+    let palettes: Vec<PaletteColor> = palette
         .iter()
-        .map(|(color, dominance)| {
+        .zip(alphas.into_iter())
+        .zip(texts.into_iter())
+        .map(|((swatch, rgba), text)| {
+            let color = &swatch.color;
             let rgb = RGB::from(color);
             PaletteColor {
-                dominance: *dominance,
+                dominance: swatch.dominance,
                 hex: rgb.hex(),
                 rgb: rgb,
+                rgba: rgba,
                 hsl: HSL::from(color),
+                hsv: HSV::from(color),
+                hwb: HWB::from(color),
+                lch: LCH::from(color),
+                role: swatch.role.map(|r| r.to_string()),
+                clipped: !in_gamut(color, RGBSpace::SRGB),
+                text: text.color.hex(),
+                text_contrast: text.contrast,
             }
         })
         .collect();
@@ -242,3 +525,149 @@ pub fn pigments(canvas: HtmlCanvasElement, k: u8, mood: Mood, batch_size: Option
     // Convert to a JS value
     return JsValue::from_serde(&palettes).unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gray(l: f32) -> LAB {
+        return LAB { l, a: 0.0, b: 0.0 };
+    }
+
+    #[test]
+    fn find_clusters_assigns_every_pixel() {
+        // 12 pixels do not split evenly over the threads
+        let points: Vec<LAB> = (0..12).map(|i| gray(i as f32 * 8.0)).collect();
+        let means = vec![gray(0.0), gray(100.0)];
+
+        let clusters = find_clusters(&points, &means, 2);
+        let mut assigned: Vec<usize> = clusters.into_iter().flatten().collect();
+        assigned.sort();
+        assert_eq!(assigned, (0..12).collect::<Vec<usize>>());
+    }
+
+    #[test]
+    fn recal_means_keeps_mean_without_weight() {
+        let points = vec![gray(20.0), gray(40.0)];
+        let mean = recal_means(&points, &[0.0, 0.0], &[0, 1], &gray(30.0));
+        assert_eq!(mean.l, 30.0);
+
+        let empty = recal_means(&points, &[1.0, 1.0], &[], &gray(50.0));
+        assert_eq!(empty.l, 50.0);
+
+        let weighted = recal_means(&points, &[1.0, 3.0], &[0, 1], &gray(30.0));
+        assert_eq!(weighted.l, 35.0);
+    }
+
+    #[test]
+    fn pigments_pixels_without_colors() {
+        let pixels: Pixels = (0..10).map(|i| gray(i as f32 * 10.0)).collect();
+        let weight = |_: &LAB| 1.0;
+
+        assert!(pigments_pixels(&pixels, 0, &weight, None).is_empty());
+        assert!(pigments_pixels(&Vec::new(), 3, &weight, None).is_empty());
+        assert_eq!(pigments_pixels(&pixels, 3, &weight, None).len(), 3);
+    }
+
+    #[test]
+    fn pigments_pixels_with_boxed_weights() {
+        use crate::weights::{resolve_mood, Mood};
+
+        let pixels: Pixels = (0..10).map(|i| gray(i as f32 * 10.0)).collect();
+        assert_eq!(pigments_pixels(&pixels, 2, resolve_mood(&Mood::Dominant), None).len(), 2);
+
+        let weight = resolve_mood(&Mood::Light);
+        assert_eq!(pigments_pixels(&pixels, 2, weight.as_ref(), None).len(), 2);
+        assert_eq!(pigments_pixels_in(&pixels, 2, weight, None, ClusterSpace::OKLab).len(), 2);
+    }
+
+    #[test]
+    fn pixels_from_rgba_skips_transparent_pixels() {
+        let white = RGB { r: 255, g: 255, b: 255 };
+        let data = [255, 0, 0, 255, 0, 0, 255, 0, 0, 0, 255, 128, 10, 20, 30, 0];
+
+        let pixels = pixels_from_rgba(&data, &white);
+        assert_eq!(pixels.len(), 2);
+        assert_eq!(RGB::from(&pixels[0]).hex(), "#FF0000");
+        assert_eq!(RGB::from(&pixels[1]).hex(), "#7F7FFF");
+
+        let grid = grid_from_rgba(&data, &white);
+        assert_eq!(grid.len(), 4);
+        assert!(grid[1].is_none() && grid[3].is_none());
+    }
+
+    #[test]
+    fn palette_alpha_averages_alpha_of_pixels() {
+        let white = RGB { r: 255, g: 255, b: 255 };
+        let pixels = vec![
+            RGBA { r: 255, g: 0, b: 0, a: 255 },
+            RGBA { r: 0, g: 0, b: 255, a: 96 },
+            RGBA { r: 0, g: 0, b: 255, a: 160 },
+            RGBA { r: 0, g: 0, b: 255, a: 0 },
+            RGBA { r: 0, g: 0, b: 0, a: 0 },
+        ];
+        let blue = RGBA { r: 0, g: 0, b: 255, a: 128 }.composite(&white);
+        let palette = vec![
+            (LAB::from_rgb(255, 0, 0), 0.3),
+            (LAB::from(&blue), 0.7),
+            (LAB::from_rgb(0, 255, 0), 0.0),
+        ];
+
+        let alphas: Vec<String> = palette_alpha(&palette, &pixels, &white).iter().map(|rgba| rgba.hex()).collect();
+        assert_eq!(alphas, ["#FF0000FF", "#0000FF80", "#00FF00FF"]);
+        assert!(palette_alpha(&[], &pixels, &white).is_empty());
+    }
+
+    #[test]
+    fn high_precision_pixels_match_8_bit_pixels() {
+        let white = RGB { r: 255, g: 255, b: 255 };
+        let space = RGBSpace::SRGB;
+        let data: Vec<u8> = (0..=255u8).flat_map(|val| vec![val, 255 - val, val / 3, 255]).collect();
+        let rgba16: Vec<u16> = data.iter().map(|val| *val as u16 * 257).collect();
+        let encoded: Vec<f32> = data.iter().map(|val| *val as f32 / 255.0).collect();
+        let linear: Vec<f32> = encoded
+            .chunks_exact(4)
+            .flat_map(|pix| vec![space.to_linear(pix[0]), space.to_linear(pix[1]), space.to_linear(pix[2]), pix[3]])
+            .collect();
+
+        let expected = grid_from_rgba(&data, &white);
+        let grids = [
+            grid_from_rgba16(&rgba16, &white),
+            grid_from_rgba32f(&encoded, &white, false),
+            grid_from_rgba32f(&linear, &white, true),
+        ];
+        for grid in grids.iter() {
+            assert_eq!(grid.len(), expected.len());
+            for (color, expected) in grid.iter().zip(expected.iter()) {
+                assert!(color.as_ref().unwrap().distance(expected.as_ref().unwrap()) < 0.01);
+            }
+        }
+
+        for val in 0..=255u8 {
+            let expected = LAB::from_rgb(val, 128, 255 - val);
+            let wide = |val: u8| val as u16 * 257;
+            assert!(LAB::from_rgb16(wide(val), wide(128), wide(255 - val)).distance(&expected) < 0.01);
+
+            let linear = |val: u8| space.to_linear(val as f32 / 255.0);
+            assert!(LAB::from_linear((linear(val), linear(128), linear(255 - val)), space).distance(&expected) < 0.01);
+        }
+    }
+
+    #[test]
+    fn high_precision_pixels_respect_alpha() {
+        let white = RGB { r: 255, g: 255, b: 255 };
+        let half = RGBA { r: 0, g: 0, b: 255, a: 128 }.composite(&white);
+
+        let grid = grid_from_rgba16(&[0, 0, 65535, 0, 0, 0, 65535, 32896], &white);
+        assert!(grid[0].is_none());
+        assert!(grid[1].as_ref().unwrap().distance(&LAB::from(&half)) < 1.0);
+
+        let grid = grid_from_rgba32f(&[0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.502], &white, false);
+        assert!(grid[0].is_none());
+        assert!(grid[1].as_ref().unwrap().distance(&LAB::from(&half)) < 1.0);
+
+        // HDR values above 1 are lighter than white
+        let grid = grid_from_rgba32f(&[4.0, 4.0, 4.0, 1.0], &white, true);
+        assert!(grid[0].as_ref().unwrap().l > 100.0);
+    }
+}
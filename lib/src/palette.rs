@@ -0,0 +1,460 @@
+use crate::{
+    accessibility::{text_color, TextColor, WCAGLevel},
+    color::{Deficiency, LAB, LCH},
+    filters::Exclude,
+    swatches::{assign_roles, Role},
+};
+use std::{cmp::Ordering, slice, str::FromStr, vec};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::prelude::*;
+
+/// Orders in which the swatches of a palette can be sorted
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    /// Most dominant first
+    Dominance,
+    /// LCh hue from 0 to 360 degrees
+    Hue,
+    /// Lightest first
+    Lightness,
+    /// Most colorful first
+    Chroma,
+    /// Path from the darkest color through the nearest colors, for smooth gradients
+    Path,
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dominance" => Ok(SortOrder::Dominance),
+            "hue" => Ok(SortOrder::Hue),
+            "lightness" => Ok(SortOrder::Lightness),
+            "chroma" => Ok(SortOrder::Chroma),
+            "path" => Ok(SortOrder::Path),
+            _ => Err(format!("Unknown sort order '{}'", s)),
+        }
+    }
+}
+
+// Euclidean distance (Delta E 1976) which is symmetric, unlike Delta E 1994
+fn euclidean(a: &LAB, b: &LAB) -> f32 {
+    return ((a.l - b.l).powi(2) + (a.a - b.a).powi(2) + (a.b - b.b).powi(2)).sqrt();
+}
+
+/// Orders the colors in a short path through LAB, which approximates the travelling salesman problem
+///
+/// Path starts from the darkest color and greedily visits the nearest unvisited color, after which
+/// crossing segments are removed by reversing them (2-opt). Returns the indices of the colors in the path.
+pub fn nearest_path(colors: &[LAB]) -> Vec<usize> {
+    if colors.is_empty() {
+        return Vec::new();
+    }
+
+    let start = (0..colors.len())
+        .min_by(|&a, &b| colors[a].l.partial_cmp(&colors[b].l).expect("NaN encountered"))
+        .unwrap();
+    let mut path = vec![start];
+    let mut remaining: Vec<usize> = (0..colors.len()).filter(|&i| i != start).collect();
+
+    while !remaining.is_empty() {
+        let last = &colors[*path.last().unwrap()];
+        let (pos, _) = remaining
+            .iter()
+            .enumerate()
+            .map(|(pos, &i)| (pos, euclidean(last, &colors[i])))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("NaN encountered"))
+            .unwrap();
+        path.push(remaining.swap_remove(pos));
+    }
+
+    // Reverse the segments from i to j whenever it shortens the open path
+    let dist = |a: usize, b: usize| euclidean(&colors[a], &colors[b]);
+    let mut improved = true;
+    while improved {
+        improved = false;
+        for i in 1..path.len() {
+            for j in (i + 1)..path.len() {
+                let before = dist(path[i - 1], path[i])
+                    + if j + 1 < path.len() { dist(path[j], path[j + 1]) } else { 0.0 };
+                let after = dist(path[i - 1], path[j])
+                    + if j + 1 < path.len() { dist(path[i], path[j + 1]) } else { 0.0 };
+
+                if after + 1e-4 < before {
+                    path[i..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+
+    return path;
+}
+
+/// Color of a palette along with its dominance and swatch role
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Swatch {
+    pub color: LAB,
+    /// Dominance of the color in the image (0 - 1)
+    pub dominance: f32,
+    pub role: Option<Role>,
+}
+
+/// Pair of swatches which become hard to tell apart with a color vision deficiency
+#[derive(Clone)]
+pub struct Confusion {
+    pub deficiency: Deficiency,
+    /// Indices of the two swatches in the palette
+    pub swatches: (usize, usize),
+    /// Delta E(1994) between the two colors as seen with the deficiency
+    pub distance: f32,
+}
+
+/// Palette of colors created from an image, which can be persisted with the `serde` feature
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Palette {
+    /// Swatches sorted by their dominance (most dominant first) unless sorted otherwise
+    pub swatches: Vec<Swatch>,
+}
+
+impl Palette {
+    /**
+     * Creates a palette from the output of `pigments_pixels` and assigns the swatch roles
+     */
+    pub fn new(colors: Vec<(LAB, f32)>) -> Self {
+        let roles = assign_roles(&colors);
+        let mut palette = Palette {
+            swatches: colors
+                .into_iter()
+                .zip(roles.into_iter())
+                .map(|((color, dominance), role)| Swatch { color, dominance, role })
+                .collect(),
+        };
+
+        palette.sort_by_dominance();
+        return palette;
+    }
+
+    /**
+     * Colors of the palette along with their dominance
+     */
+    pub fn colors(&self) -> Vec<(LAB, f32)> {
+        return self.swatches
+            .iter()
+            .map(|swatch| (swatch.color.clone(), swatch.dominance))
+            .collect();
+    }
+
+    /**
+     * Converts the colors of the palette to another color space like `RGB` or `HSL`
+     */
+    pub fn convert<T>(&self) -> Vec<T> where T: for<'a> From<&'a LAB> {
+        return self.swatches.iter().map(|swatch| T::from(&swatch.color)).collect();
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, Swatch> {
+        return self.swatches.iter();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.swatches.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.swatches.is_empty();
+    }
+
+    /**
+     * Swatch whose color is the nearest (by Delta E) to the given color
+     */
+    pub fn nearest(&self, color: &LAB) -> Option<&Swatch> {
+        return self.swatches
+            .iter()
+            .map(|swatch| (swatch, swatch.color.distance(color)))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).expect("NaN encountered"))
+            .map(|(swatch, _)| swatch);
+    }
+
+    /**
+     * Swatch which has the given role, if it was assigned
+     */
+    pub fn swatch(&self, role: Role) -> Option<&Swatch> {
+        return self.swatches.iter().find(|swatch| swatch.role == Some(role));
+    }
+
+    /**
+     * Suggests a text color for every swatch meeting the WCAG level, in the order of the swatches
+     *
+     * Text colors are tinted with the colors of the palette when possible, otherwise black or white is used.
+     */
+    pub fn text_colors(&self, level: WCAGLevel) -> Vec<TextColor> {
+        let colors: Vec<LAB> = self.swatches.iter().map(|swatch| swatch.color.clone()).collect();
+        return colors.iter().map(|color| text_color(color, &colors, level.ratio())).collect();
+    }
+
+    /**
+     * Finds the pairs of swatches which become indistinguishable with the color vision deficiency
+     *
+     * A pair is reported when the Delta E(1994) between its simulated colors is below the `threshold`
+     * while the difference between its original colors is not.
+     */
+    pub fn confusions(&self, deficiency: Deficiency, threshold: f32) -> Vec<Confusion> {
+        let simulated: Vec<LAB> = self.swatches.iter().map(|swatch| swatch.color.simulate(deficiency)).collect();
+        let mut confusions = Vec::new();
+
+        for i in 0..self.swatches.len() {
+            for j in (i + 1)..self.swatches.len() {
+                let distance = simulated[i].distance(&simulated[j]);
+                if distance < threshold && self.swatches[i].color.distance(&self.swatches[j].color) >= threshold {
+                    confusions.push(Confusion { deficiency, swatches: (i, j), distance });
+                }
+            }
+        }
+
+        return confusions;
+    }
+
+    /**
+     * Finds the pairs of swatches which become indistinguishable with every simulated color vision deficiency
+     */
+    pub fn check_deficiencies(&self, threshold: f32) -> Vec<Confusion> {
+        return Deficiency::ALL
+            .iter()
+            .flat_map(|deficiency| self.confusions(*deficiency, threshold))
+            .collect();
+    }
+
+    /**
+     * Sorts the swatches with a comparator
+     */
+    pub fn sort_by<F>(&mut self, compare: F) where F: FnMut(&Swatch, &Swatch) -> Ordering {
+        self.swatches.sort_by(compare);
+    }
+
+    /**
+     * Sorts the swatches by their dominance, most dominant first
+     */
+    pub fn sort_by_dominance(&mut self) {
+        self.sort_by(|a, b| b.dominance.partial_cmp(&a.dominance).expect("NaN encountered"));
+    }
+
+    /**
+     * Sorts the swatches by their lightness, lightest first
+     */
+    pub fn sort_by_lightness(&mut self) {
+        self.sort_by(|a, b| b.color.l.partial_cmp(&a.color.l).expect("NaN encountered"));
+    }
+
+    /**
+     * Sorts the swatches by their chroma, most colorful first
+     */
+    pub fn sort_by_chroma(&mut self) {
+        self.sort_by(|a, b| b.color.chroma().partial_cmp(&a.color.chroma()).expect("NaN encountered"));
+    }
+
+    /**
+     * Sorts the swatches by their LCh hue from 0 to 360 degrees
+     */
+    pub fn sort_by_hue(&mut self) {
+        self.sort_by(|a, b| {
+            LCH::from(&a.color).h.partial_cmp(&LCH::from(&b.color).h).expect("NaN encountered")
+        });
+    }
+
+    /**
+     * Sorts the swatches by a path through their nearest colors, starting from the darkest color
+     */
+    pub fn sort_by_path(&mut self) {
+        let colors: Vec<LAB> = self.swatches.iter().map(|swatch| swatch.color.clone()).collect();
+        let mut swatches: Vec<Option<Swatch>> = self.swatches.drain(..).map(Some).collect();
+
+        self.swatches = nearest_path(&colors)
+            .into_iter()
+            .map(|i| swatches[i].take().unwrap())
+            .collect();
+    }
+
+    /**
+     * Sorts the swatches in the given order
+     */
+    pub fn sort(&mut self, order: SortOrder) {
+        match order {
+            SortOrder::Dominance => self.sort_by_dominance(),
+            SortOrder::Hue => self.sort_by_hue(),
+            SortOrder::Lightness => self.sort_by_lightness(),
+            SortOrder::Chroma => self.sort_by_chroma(),
+            SortOrder::Path => self.sort_by_path(),
+        }
+    }
+
+    /**
+     * Keeps the swatches matching the predicate
+     *
+     * The roles are reassigned to the remaining swatches, so the roles of removed swatches can be taken by others.
+     */
+    pub fn filter<P>(mut self, predicate: P) -> Self where P: FnMut(&Swatch) -> bool {
+        self.swatches = self.swatches.into_iter().filter(predicate).collect();
+
+        let roles = assign_roles(&self.colors());
+        for (swatch, role) in self.swatches.iter_mut().zip(roles.into_iter()) {
+            swatch.role = role;
+        }
+        return self;
+    }
+
+    /**
+     * Removes the swatches whose dominance (0 - 1) is below the `floor`
+     */
+    pub fn min_dominance(self, floor: f32) -> Self {
+        return self.filter(|swatch| swatch.dominance >= floor);
+    }
+
+    /**
+     * Removes the swatches matching any of the exclusions
+     */
+    pub fn exclude(self, excludes: &[Exclude]) -> Self {
+        return self.filter(|swatch| !excludes.iter().any(|exclude| exclude.matches(&swatch.color)));
+    }
+}
+
+impl IntoIterator for Palette {
+    type Item = Swatch;
+    type IntoIter = vec::IntoIter<Swatch>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.swatches.into_iter();
+    }
+}
+
+impl<'a> IntoIterator for &'a Palette {
+    type Item = &'a Swatch;
+    type IntoIter = slice::Iter<'a, Swatch>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        return self.swatches.iter();
+    }
+}
+
+impl From<Vec<(LAB, f32)>> for Palette {
+    fn from(colors: Vec<(LAB, f32)>) -> Self {
+        return Palette::new(colors);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::RGB;
+
+    fn gray(l: f32) -> LAB {
+        return LAB { l, a: 0.0, b: 0.0 };
+    }
+
+    #[test]
+    fn nearest_path_of_few_colors() {
+        assert!(nearest_path(&[]).is_empty());
+        assert_eq!(nearest_path(&[gray(50.0)]), vec![0]);
+        assert_eq!(nearest_path(&[gray(80.0), gray(20.0)]), vec![1, 0]);
+    }
+
+    #[test]
+    fn nearest_path_visits_every_color_from_the_darkest() {
+        // Grays are visited from dark to light
+        let grays: Vec<LAB> = [90.0, 10.0, 50.0, 30.0, 70.0, 20.0, 60.0].iter().map(|l| gray(*l)).collect();
+        assert_eq!(nearest_path(&grays), vec![1, 5, 3, 2, 6, 4, 0]);
+
+        let colors: Vec<LAB> = (0..20)
+            .map(|i| LAB { l: (i * 37 % 100) as f32, a: (i * 53 % 120) as f32 - 60.0, b: (i * 71 % 120) as f32 - 60.0 })
+            .collect();
+        let path = nearest_path(&colors);
+
+        let mut visited = path.clone();
+        visited.sort();
+        assert_eq!(visited, (0..colors.len()).collect::<Vec<usize>>());
+        assert_eq!(colors[path[0]].l, 0.0);
+
+        // No reversed segment shortens the path after 2-opt
+        let length = |path: &[usize]| path.windows(2).map(|w| euclidean(&colors[w[0]], &colors[w[1]])).sum::<f32>();
+        for i in 1..path.len() {
+            for j in (i + 1)..path.len() {
+                let mut reversed = path.clone();
+                reversed[i..=j].reverse();
+                assert!(length(&reversed) + 1e-3 >= length(&path));
+            }
+        }
+    }
+
+    #[test]
+    fn sorts_swatches() {
+        let lab = |r, g, b| LAB::from(&RGB { r, g, b });
+        let mut palette = Palette::new(vec![
+            (lab(0, 0, 255), 0.1),
+            (lab(255, 0, 0), 0.2),
+            (lab(128, 128, 128), 0.3),
+            (lab(0, 255, 0), 0.4),
+        ]);
+        let hex = |palette: &Palette| palette.convert::<RGB>().iter().map(|rgb| rgb.hex()).collect::<Vec<String>>();
+        assert_eq!(hex(&palette), ["#00FF00", "#808080", "#FF0000", "#0000FF"]);
+
+        // Hue of gray is meaningless, so only the order of the other colors is checked
+        palette.sort(SortOrder::Hue);
+        let hues: Vec<String> = hex(&palette).into_iter().filter(|hex| hex != "#808080").collect();
+        assert_eq!(hues, ["#FF0000", "#00FF00", "#0000FF"]);
+        palette.sort(SortOrder::Lightness);
+        assert_eq!(hex(&palette), ["#00FF00", "#808080", "#FF0000", "#0000FF"]);
+        palette.sort(SortOrder::Chroma);
+        assert_eq!(hex(&palette), ["#0000FF", "#00FF00", "#FF0000", "#808080"]);
+        palette.sort(SortOrder::Path);
+        assert_eq!(hex(&palette)[0], "#0000FF");
+        assert_eq!(palette.len(), 4);
+        palette.sort(SortOrder::Dominance);
+        assert_eq!(hex(&palette), ["#00FF00", "#808080", "#FF0000", "#0000FF"]);
+    }
+
+    #[test]
+    fn reassigns_roles_after_filtering() {
+        let lab = |r, g, b| LAB::from(&RGB { r, g, b });
+        let palette = Palette::new(vec![
+            (lab(230, 30, 30), 0.5),
+            (lab(200, 40, 40), 0.3),
+            (lab(120, 130, 140), 0.2),
+        ]);
+        assert!(palette.swatches[0].role == Some(Role::Vibrant));
+        assert!(palette.swatches[1].role != Some(Role::Vibrant));
+
+        // Role of the removed swatch is taken by the next best swatch
+        let filtered = palette.exclude(&[Exclude::Color(lab(230, 30, 30), 1.0)]);
+        assert_eq!(filtered.len(), 2);
+        assert!(filtered.swatches[0].role == Some(Role::Vibrant));
+        assert!(filtered.swatch(Role::Muted).is_some());
+    }
+
+    #[test]
+    fn finds_colors_confused_with_deficiencies() {
+        let lab = |r, g, b| LAB::from(&RGB { r, g, b });
+        let palette = Palette::new(vec![
+            (lab(204, 51, 17), 0.4),
+            (lab(102, 153, 17), 0.3),
+            (lab(17, 51, 204), 0.2),
+            (lab(255, 255, 255), 0.1),
+        ]);
+
+        // Red and green are only confused with deuteranopia
+        let confusions = palette.check_deficiencies(10.0);
+        assert_eq!(confusions.len(), 1);
+        assert!(confusions[0].deficiency == Deficiency::Deuteranopia);
+        assert_eq!(confusions[0].swatches, (0, 1));
+        assert!(confusions[0].distance < 10.0);
+
+        // Pairs which are already similar are not reported
+        assert!(palette.confusions(Deficiency::Achromatopsia, 200.0).is_empty());
+    }
+}
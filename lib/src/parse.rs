@@ -0,0 +1,494 @@
+use crate::color::{RGB, RGBA, LAB, HSL, HWB, LCH, OKLAB, OKLCH, WhitePoint};
+use std::{error, fmt, str::FromStr};
+
+/// Error while parsing a color from a string
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseColorError {
+    /// String is empty
+    Empty,
+    /// Hex color code has an invalid length or digits
+    InvalidHex(String),
+    /// Color function like `rgb()` is unknown or is not closed
+    InvalidFunction(String),
+    /// Color function has the wrong number of arguments
+    ArgumentCount { function: String, expected: usize, found: usize },
+    /// Argument of a color function is not a valid number, percentage or angle
+    InvalidArgument(String),
+    /// Name is not a known CSS color name
+    UnknownName(String),
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColorError::Empty => write!(f, "Color is empty"),
+            ParseColorError::InvalidHex(hex) => write!(f, "Invalid hex color code '{}'", hex),
+            ParseColorError::InvalidFunction(func) => write!(f, "Invalid color function '{}'", func),
+            ParseColorError::ArgumentCount { function, expected, found } => write!(
+                f,
+                "Color function '{}' expects {} arguments, found {}",
+                function, expected, found
+            ),
+            ParseColorError::InvalidArgument(arg) => write!(f, "Invalid color argument '{}'", arg),
+            ParseColorError::UnknownName(name) => write!(f, "Unknown color name '{}'", name),
+        }
+    }
+}
+
+impl error::Error for ParseColorError {}
+
+/// Color parsed from a CSS color string in the color space it was written in
+///
+/// CSS defines `lab()` and `lch()` relative to D50, their values are adapted to D65 like the rest of the crate.
+#[derive(Clone)]
+pub enum CSSColor {
+    RGB(RGB),
+    HSL(HSL),
+    HWB(HWB),
+    LAB(LAB),
+    LCH(LCH),
+    OKLAB(OKLAB),
+    OKLCH(OKLCH),
+}
+
+/// Argument of a color function
+enum Arg {
+    Number(f32),
+    Percent(f32),
+    /// Angle in degrees
+    Angle(f32),
+}
+
+impl Arg {
+    fn parse(token: &str) -> Result<Self, ParseColorError> {
+        let invalid = || ParseColorError::InvalidArgument(String::from(token));
+        let number = |s: &str| s.parse::<f32>().map_err(|_| invalid());
+
+        let token = token.trim().to_lowercase();
+        if token == "none" {
+            return Ok(Arg::Number(0.0));
+        }
+        if let Some(val) = token.strip_suffix('%') {
+            return Ok(Arg::Percent(number(val)?));
+        }
+        if let Some(val) = token.strip_suffix("deg") {
+            return Ok(Arg::Angle(number(val)?));
+        }
+        if let Some(val) = token.strip_suffix("grad") {
+            return Ok(Arg::Angle(number(val)? * 0.9));
+        }
+        if let Some(val) = token.strip_suffix("rad") {
+            return Ok(Arg::Angle(number(val)?.to_degrees()));
+        }
+        if let Some(val) = token.strip_suffix("turn") {
+            return Ok(Arg::Angle(number(val)? * 360.0));
+        }
+
+        return Ok(Arg::Number(number(&token)?));
+    }
+
+    /**
+     * Value of the argument where 100% maps to `full`
+     */
+    fn value(&self, full: f32) -> f32 {
+        match self {
+            Arg::Number(val) | Arg::Angle(val) => *val,
+            Arg::Percent(val) => val / 100.0 * full,
+        }
+    }
+
+    /**
+     * Hue of the argument normalized to 0 - 360 degrees
+     */
+    fn hue(&self) -> f32 {
+        let hue = match self {
+            Arg::Number(val) | Arg::Angle(val) => *val,
+            Arg::Percent(val) => val / 100.0 * 360.0,
+        };
+        return hue.rem_euclid(360.0);
+    }
+}
+
+/// Parses a hex color code with optional leading '#' into color and alpha
+pub(crate) fn parse_hex(hex: &str) -> Result<(RGB, f32), ParseColorError> {
+    let invalid = || ParseColorError::InvalidHex(String::from(hex));
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    // Short forms like #rgb have each digit repeated
+    let expanded: String = match digits.len() {
+        3 | 4 => digits.chars().flat_map(|c| vec![c, c]).collect(),
+        6 | 8 => String::from(digits),
+        _ => return Err(invalid()),
+    };
+
+    let channel = |i: usize| u8::from_str_radix(&expanded[i..i + 2], 16).map_err(|_| invalid());
+    let alpha = if expanded.len() == 8 { channel(6)? as f32 / 255.0 } else { 1.0 };
+
+    return Ok((RGB { r: channel(0)?, g: channel(2)?, b: channel(4)? }, alpha));
+}
+
+/// Parses the arguments of a color function into 3 channel arguments and alpha
+fn parse_args(function: &str, args: &str) -> Result<([Arg; 3], f32), ParseColorError> {
+    // Modern syntax separates alpha using '/', legacy syntax uses a 4th comma separated argument
+    let (channels, alpha) = match args.find('/') {
+        Some(i) => (&args[..i], Some(&args[i + 1..])),
+        None => (args, None),
+    };
+
+    let mut tokens: Vec<&str> = if channels.contains(',') {
+        channels.split(',').map(|t| t.trim()).collect()
+    } else {
+        channels.split_whitespace().collect()
+    };
+
+    let alpha = match (alpha, tokens.len()) {
+        (Some(a), _) => Some(a.trim()),
+        (None, 4) if channels.contains(',') => tokens.pop(),
+        _ => None,
+    };
+
+    if tokens.len() != 3 {
+        return Err(ParseColorError::ArgumentCount {
+            function: String::from(function),
+            expected: 3,
+            found: tokens.len(),
+        });
+    }
+
+    let alpha = match alpha {
+        Some(a) => Arg::parse(a)?.value(1.0).max(0.0).min(1.0),
+        None => 1.0,
+    };
+
+    return Ok(([Arg::parse(tokens[0])?, Arg::parse(tokens[1])?, Arg::parse(tokens[2])?], alpha));
+}
+
+// Rounds and clamps a channel to 8-bit
+fn channel(val: f32) -> u8 {
+    val.round().max(0.0).min(255.0) as u8
+}
+
+/// Parses a CSS color string into the color and its alpha (0 - 1)
+///
+/// Supported formats are hex color codes (`#rgb`, `#rgba`, `#rrggbb` and `#rrggbbaa`),
+/// the color functions `rgb()`, `rgba()`, `hsl()`, `hsla()`, `hwb()`, `lab()`, `lch()`, `oklab()`
+/// and `oklch()` in both legacy (comma separated) and modern syntax, and CSS named colors.
+pub fn parse_color(s: &str) -> Result<(CSSColor, f32), ParseColorError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(ParseColorError::Empty);
+    }
+
+    if s.starts_with('#') {
+        let (rgb, alpha) = parse_hex(s)?;
+        return Ok((CSSColor::RGB(rgb), alpha));
+    }
+
+    let open = match s.find('(') {
+        Some(i) => i,
+        None => {
+            let name = s.to_lowercase();
+            if let Some((_, hex)) = NAMED_COLORS.iter().find(|(n, _)| *n == name) {
+                let alpha = if name == "transparent" { 0.0 } else { 1.0 };
+                return Ok((CSSColor::RGB(parse_hex(hex)?.0), alpha));
+            }
+
+            // Hex color codes need the leading '#', so a misspelled name like 'bad' is not read as hex
+            return Err(ParseColorError::UnknownName(String::from(s)));
+        },
+    };
+
+    if !s.ends_with(')') {
+        return Err(ParseColorError::InvalidFunction(String::from(s)));
+    }
+
+    let function = s[..open].trim().to_lowercase();
+    let ([x, y, z], alpha) = parse_args(&function, &s[open + 1..s.len() - 1])?;
+
+    let color = match function.as_str() {
+        "rgb" | "rgba" => CSSColor::RGB(RGB {
+            r: channel(x.value(255.0)),
+            g: channel(y.value(255.0)),
+            b: channel(z.value(255.0)),
+        }),
+        "hsl" | "hsla" => CSSColor::HSL(HSL {
+            h: x.hue() / 360.0,
+            s: (y.value(100.0) / 100.0).max(0.0).min(1.0),
+            l: (z.value(100.0) / 100.0).max(0.0).min(1.0),
+        }),
+        "hwb" => CSSColor::HWB(HWB {
+            h: x.hue() / 360.0,
+            w: (y.value(100.0) / 100.0).max(0.0).min(1.0),
+            b: (z.value(100.0) / 100.0).max(0.0).min(1.0),
+        }),
+        "lab" => CSSColor::LAB(LAB {
+            l: x.value(100.0),
+            a: y.value(125.0),
+            b: z.value(125.0),
+        }.adapt(WhitePoint::D50, WhitePoint::D65)),
+        "lch" => {
+            let d50 = LAB::from(&LCH { l: x.value(100.0), c: y.value(150.0), h: z.hue() });
+            CSSColor::LCH(LCH::from(&d50.adapt(WhitePoint::D50, WhitePoint::D65)))
+        },
+        "oklab" => CSSColor::OKLAB(OKLAB {
+            l: x.value(1.0),
+            a: y.value(0.4),
+            b: z.value(0.4),
+        }),
+        "oklch" => CSSColor::OKLCH(OKLCH {
+            l: x.value(1.0),
+            c: y.value(0.4),
+            h: z.hue(),
+        }),
+        _ => return Err(ParseColorError::InvalidFunction(function)),
+    };
+
+    return Ok((color, alpha));
+}
+
+impl From<&CSSColor> for RGB {
+    fn from(color: &CSSColor) -> Self {
+        match color {
+            CSSColor::RGB(c) => c.clone(),
+            CSSColor::HSL(c) => RGB::from(c),
+            CSSColor::HWB(c) => RGB::from(c),
+            CSSColor::LAB(c) => RGB::from(c),
+            CSSColor::LCH(c) => RGB::from(c),
+            CSSColor::OKLAB(c) => RGB::from(c),
+            CSSColor::OKLCH(c) => RGB::from(c),
+        }
+    }
+}
+
+impl From<&CSSColor> for LAB {
+    fn from(color: &CSSColor) -> Self {
+        match color {
+            CSSColor::LAB(c) => c.clone(),
+            CSSColor::LCH(c) => LAB::from(c),
+            CSSColor::OKLAB(c) => LAB::from(c),
+            CSSColor::OKLCH(c) => LAB::from(c),
+            _ => LAB::from(&RGB::from(color)),
+        }
+    }
+}
+
+impl FromStr for RGB {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(RGB::from(&parse_color(s)?.0));
+    }
+}
+
+impl FromStr for RGBA {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (color, alpha) = parse_color(s)?;
+        let rgb = RGB::from(&color);
+        return Ok(RGBA { r: rgb.r, g: rgb.g, b: rgb.b, a: (alpha * 255.0).round() as u8 });
+    }
+}
+
+impl FromStr for LAB {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Ok(LAB::from(&parse_color(s)?.0));
+    }
+}
+
+impl FromStr for HSL {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_color(s)?.0 {
+            CSSColor::HSL(c) => Ok(c),
+            color => Ok(HSL::from(&RGB::from(&color))),
+        };
+    }
+}
+
+impl FromStr for HWB {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_color(s)?.0 {
+            CSSColor::HWB(c) => Ok(c),
+            color => Ok(HWB::from(&RGB::from(&color))),
+        };
+    }
+}
+
+impl FromStr for LCH {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_color(s)?.0 {
+            CSSColor::LCH(c) => Ok(c),
+            color => Ok(LCH::from(&LAB::from(&color))),
+        };
+    }
+}
+
+impl FromStr for OKLAB {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_color(s)?.0 {
+            CSSColor::OKLAB(c) => Ok(c),
+            CSSColor::OKLCH(c) => Ok(OKLAB::from(&c)),
+            color => Ok(OKLAB::from(&LAB::from(&color))),
+        };
+    }
+}
+
+impl FromStr for OKLCH {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return match parse_color(s)?.0 {
+            CSSColor::OKLCH(c) => Ok(c),
+            CSSColor::OKLAB(c) => Ok(OKLCH::from(&c)),
+            color => Ok(OKLCH::from(&LAB::from(&color))),
+        };
+    }
+}
+
+/// CSS named colors from https://www.w3.org/TR/css-color-4/#named-colors
+const NAMED_COLORS: [(&str, &str); 149] = [
+    ("aliceblue", "#f0f8ff"), ("antiquewhite", "#faebd7"), ("aqua", "#00ffff"),
+    ("aquamarine", "#7fffd4"), ("azure", "#f0ffff"), ("beige", "#f5f5dc"),
+    ("bisque", "#ffe4c4"), ("black", "#000000"), ("blanchedalmond", "#ffebcd"),
+    ("blue", "#0000ff"), ("blueviolet", "#8a2be2"), ("brown", "#a52a2a"),
+    ("burlywood", "#deb887"), ("cadetblue", "#5f9ea0"), ("chartreuse", "#7fff00"),
+    ("chocolate", "#d2691e"), ("coral", "#ff7f50"), ("cornflowerblue", "#6495ed"),
+    ("cornsilk", "#fff8dc"), ("crimson", "#dc143c"), ("cyan", "#00ffff"),
+    ("darkblue", "#00008b"), ("darkcyan", "#008b8b"), ("darkgoldenrod", "#b8860b"),
+    ("darkgray", "#a9a9a9"), ("darkgreen", "#006400"), ("darkgrey", "#a9a9a9"),
+    ("darkkhaki", "#bdb76b"), ("darkmagenta", "#8b008b"), ("darkolivegreen", "#556b2f"),
+    ("darkorange", "#ff8c00"), ("darkorchid", "#9932cc"), ("darkred", "#8b0000"),
+    ("darksalmon", "#e9967a"), ("darkseagreen", "#8fbc8f"), ("darkslateblue", "#483d8b"),
+    ("darkslategray", "#2f4f4f"), ("darkslategrey", "#2f4f4f"), ("darkturquoise", "#00ced1"),
+    ("darkviolet", "#9400d3"), ("deeppink", "#ff1493"), ("deepskyblue", "#00bfff"),
+    ("dimgray", "#696969"), ("dimgrey", "#696969"), ("dodgerblue", "#1e90ff"),
+    ("firebrick", "#b22222"), ("floralwhite", "#fffaf0"), ("forestgreen", "#228b22"),
+    ("fuchsia", "#ff00ff"), ("gainsboro", "#dcdcdc"), ("ghostwhite", "#f8f8ff"),
+    ("gold", "#ffd700"), ("goldenrod", "#daa520"), ("gray", "#808080"),
+    ("green", "#008000"), ("greenyellow", "#adff2f"), ("grey", "#808080"),
+    ("honeydew", "#f0fff0"), ("hotpink", "#ff69b4"), ("indianred", "#cd5c5c"),
+    ("indigo", "#4b0082"), ("ivory", "#fffff0"), ("khaki", "#f0e68c"),
+    ("lavender", "#e6e6fa"), ("lavenderblush", "#fff0f5"), ("lawngreen", "#7cfc00"),
+    ("lemonchiffon", "#fffacd"), ("lightblue", "#add8e6"), ("lightcoral", "#f08080"),
+    ("lightcyan", "#e0ffff"), ("lightgoldenrodyellow", "#fafad2"), ("lightgray", "#d3d3d3"),
+    ("lightgreen", "#90ee90"), ("lightgrey", "#d3d3d3"), ("lightpink", "#ffb6c1"),
+    ("lightsalmon", "#ffa07a"), ("lightseagreen", "#20b2aa"), ("lightskyblue", "#87cefa"),
+    ("lightslategray", "#778899"), ("lightslategrey", "#778899"), ("lightsteelblue", "#b0c4de"),
+    ("lightyellow", "#ffffe0"), ("lime", "#00ff00"), ("limegreen", "#32cd32"),
+    ("linen", "#faf0e6"), ("magenta", "#ff00ff"), ("maroon", "#800000"),
+    ("mediumaquamarine", "#66cdaa"), ("mediumblue", "#0000cd"), ("mediumorchid", "#ba55d3"),
+    ("mediumpurple", "#9370db"), ("mediumseagreen", "#3cb371"), ("mediumslateblue", "#7b68ee"),
+    ("mediumspringgreen", "#00fa9a"), ("mediumturquoise", "#48d1cc"), ("mediumvioletred", "#c71585"),
+    ("midnightblue", "#191970"), ("mintcream", "#f5fffa"), ("mistyrose", "#ffe4e1"),
+    ("moccasin", "#ffe4b5"), ("navajowhite", "#ffdead"), ("navy", "#000080"),
+    ("oldlace", "#fdf5e6"), ("olive", "#808000"), ("olivedrab", "#6b8e23"),
+    ("orange", "#ffa500"), ("orangered", "#ff4500"), ("orchid", "#da70d6"),
+    ("palegoldenrod", "#eee8aa"), ("palegreen", "#98fb98"), ("paleturquoise", "#afeeee"),
+    ("palevioletred", "#db7093"), ("papayawhip", "#ffefd5"), ("peachpuff", "#ffdab9"),
+    ("peru", "#cd853f"), ("pink", "#ffc0cb"), ("plum", "#dda0dd"),
+    ("powderblue", "#b0e0e6"), ("purple", "#800080"), ("rebeccapurple", "#663399"),
+    ("red", "#ff0000"), ("rosybrown", "#bc8f8f"), ("royalblue", "#4169e1"),
+    ("saddlebrown", "#8b4513"), ("salmon", "#fa8072"), ("sandybrown", "#f4a460"),
+    ("seagreen", "#2e8b57"), ("seashell", "#fff5ee"), ("sienna", "#a0522d"),
+    ("silver", "#c0c0c0"), ("skyblue", "#87ceeb"), ("slateblue", "#6a5acd"),
+    ("slategray", "#708090"), ("slategrey", "#708090"), ("snow", "#fffafa"),
+    ("springgreen", "#00ff7f"), ("steelblue", "#4682b4"), ("tan", "#d2b48c"),
+    ("teal", "#008080"), ("thistle", "#d8bfd8"), ("tomato", "#ff6347"),
+    ("transparent", "#000000"), ("turquoise", "#40e0d0"), ("violet", "#ee82ee"),
+    ("wheat", "#f5deb3"), ("white", "#ffffff"), ("whitesmoke", "#f5f5f5"),
+    ("yellow", "#ffff00"), ("yellowgreen", "#9acd32"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_hex_codes() {
+        assert_eq!("#ff0000".parse::<RGB>().unwrap().hex(), "#FF0000");
+        assert_eq!("#0f08".parse::<RGBA>().unwrap().hex(), "#00FF0088");
+        assert_eq!(RGB::from_hex("00ff00").unwrap().hex(), "#00FF00");
+        assert!("##fff".parse::<RGB>().is_err());
+        assert!("#ff00".parse::<RGB>().is_ok());
+        assert!("#ff000".parse::<RGB>().is_err());
+    }
+
+    #[test]
+    fn parses_functions_in_both_syntaxes() {
+        assert_eq!("rgb(255, 0, 0)".parse::<RGB>().unwrap().hex(), "#FF0000");
+        assert_eq!("rgb(100% 0% 0% / 50%)".parse::<RGBA>().unwrap().hex(), "#FF000080");
+        assert_eq!("hsl(120deg 100% 50%)".parse::<RGB>().unwrap().hex(), "#00FF00");
+        assert_eq!("hsla(240, 100%, 50%, 0.5)".parse::<RGBA>().unwrap().hex(), "#0000FF80");
+        assert_eq!("hwb(0 0% 0%)".parse::<RGB>().unwrap().hex(), "#FF0000");
+        assert_eq!("oklab(0.628 0.2249 0.1258)".parse::<RGB>().unwrap().hex(), "#FF0000");
+        assert_eq!("oklch(0.628 0.2577 29.23)".parse::<RGB>().unwrap().hex(), "#FF0000");
+        assert_eq!("rebeccapurple".parse::<RGB>().unwrap().hex(), "#663399");
+    }
+
+    #[test]
+    fn parses_lab_and_lch_relative_to_d50() {
+        // sRGB red is lab(54.29 80.8 69.89) and lch(54.29 106.84 40.85) in CSS
+        assert_eq!("lab(54.29 80.8 69.89)".parse::<RGB>().unwrap().hex(), "#FF0000");
+        assert_eq!("lch(54.29 106.84 40.85)".parse::<RGB>().unwrap().hex(), "#FF0000");
+
+        let red = "lab(54.29 80.8 69.89)".parse::<LAB>().unwrap();
+        let expected = LAB::from(&RGB { r: 255, g: 0, b: 0 });
+        assert!(red.distance(&expected) < 0.1);
+    }
+
+    #[test]
+    fn maps_oklch_outside_the_gamut() {
+        // Green of Display P3 is outside sRGB, clipping it would shift its hue
+        let color = "oklch(0.85 0.37 145)".parse::<OKLCH>().unwrap();
+        let rgb = "oklch(0.85 0.37 145)".parse::<RGB>().unwrap();
+        let (clipped, _) = crate::gamut::clip(&LAB::from(&color), crate::color::RGBSpace::SRGB);
+        assert_ne!(rgb.hex(), clipped.hex());
+
+        let mapped = OKLCH::from(&rgb);
+        assert!((mapped.h - color.h).abs() < 3.0, "hue of {} moved to {}", color.h, mapped.h);
+        assert!((mapped.l - color.l).abs() < 0.03, "lightness of {} moved to {}", color.l, mapped.l);
+        assert_eq!(RGB::from(&OKLAB::from(&color)).hex(), rgb.hex());
+    }
+
+    #[test]
+    fn parses_hwb() {
+        let hwb = "hwb(120 20% 30%)".parse::<HWB>().unwrap();
+        assert!((hwb.h - 1.0 / 3.0).abs() < 1e-6);
+        assert!((hwb.w - 0.2).abs() < 1e-6);
+        assert!((hwb.b - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn reports_errors() {
+        assert_eq!("".parse::<RGB>().err(), Some(ParseColorError::Empty));
+        assert_eq!(
+            "rgb(1 2)".parse::<RGB>().err(),
+            Some(ParseColorError::ArgumentCount { function: String::from("rgb"), expected: 3, found: 2 })
+        );
+        assert!(matches!("rgb(1 2 x)".parse::<RGB>(), Err(ParseColorError::InvalidArgument(_))));
+        assert!(matches!("foo(1 2 3)".parse::<RGB>(), Err(ParseColorError::InvalidFunction(_))));
+        assert!(matches!("notacolor".parse::<RGB>(), Err(ParseColorError::UnknownName(_))));
+    }
+
+    #[test]
+    fn requires_hash_for_hex_codes() {
+        for word in ["bad", "cafe", "add", "00ff00", "ff000080"].iter() {
+            assert!(matches!(word.parse::<RGB>(), Err(ParseColorError::UnknownName(_))), "{} is parsed", word);
+        }
+        assert_eq!("#cafe".parse::<RGBA>().unwrap().hex(), "#CCAAFFEE");
+        assert_eq!("Tan".parse::<RGB>().unwrap().hex(), "#D2B48C");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::color::{LAB, HSL};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// Named roles of the swatches in a palette, similar to Android's Palette API
+#[derive(Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Role {
+    LightVibrant,
+    Vibrant,
+    DarkVibrant,
+    LightMuted,
+    Muted,
+    DarkMuted,
+}
+
+/// Importance of each property while scoring a swatch for a role
+const SATURATION_WEIGHT: f32 = 0.24;
+const LIGHTNESS_WEIGHT: f32 = 0.52;
+const DOMINANCE_WEIGHT: f32 = 0.24;
+
+/// Order in which the roles are assigned
+const ROLES: [Role; 6] = [
+    Role::LightVibrant,
+    Role::Vibrant,
+    Role::DarkVibrant,
+    Role::LightMuted,
+    Role::Muted,
+    Role::DarkMuted,
+];
+
+/// Target values of saturation or lightness
+struct Target {
+    min: f32,
+    target: f32,
+    max: f32,
+}
+
+impl Target {
+    fn contains(&self, val: f32) -> bool {
+        val >= self.min && val <= self.max
+    }
+}
+
+impl Role {
+    /**
+     * Target HSL saturation of the role
+     */
+    fn saturation(&self) -> Target {
+        match self {
+            Role::LightVibrant | Role::Vibrant | Role::DarkVibrant => Target { min: 0.35, target: 1.0, max: 1.0 },
+            Role::LightMuted | Role::Muted | Role::DarkMuted => Target { min: 0.0, target: 0.3, max: 0.4 },
+        }
+    }
+
+    /**
+     * Target HSL lightness of the role
+     */
+    fn lightness(&self) -> Target {
+        match self {
+            Role::LightVibrant | Role::LightMuted => Target { min: 0.55, target: 0.74, max: 1.0 },
+            Role::Vibrant | Role::Muted => Target { min: 0.3, target: 0.5, max: 0.7 },
+            Role::DarkVibrant | Role::DarkMuted => Target { min: 0.0, target: 0.26, max: 0.45 },
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Role::LightVibrant => "Light Vibrant",
+            Role::Vibrant => "Vibrant",
+            Role::DarkVibrant => "Dark Vibrant",
+            Role::LightMuted => "Light Muted",
+            Role::Muted => "Muted",
+            Role::DarkMuted => "Dark Muted",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Assigns a role to the colors of a palette
+///
+/// For every role, the color within its target saturation and lightness ranges that scores the highest
+/// is picked, based on the closeness to the targets and its dominance. A color is assigned at most one role
+/// and the returned vector has the role (if any) of each color in the palette.
+pub fn assign_roles(palette: &[(LAB, f32)]) -> Vec<Option<Role>> {
+    let mut roles: Vec<Option<Role>> = vec![None; palette.len()];
+    let hsl: Vec<HSL> = palette.iter().map(|(color, _)| HSL::from(color)).collect();
+    let max_dominance = palette
+        .iter()
+        .map(|(_, dominance)| *dominance)
+        .fold(f32::EPSILON, f32::max);
+
+    for role in ROLES.iter() {
+        let saturation = role.saturation();
+        let lightness = role.lightness();
+
+        let best = (0..palette.len())
+            .filter(|&i| roles[i].is_none())
+            .filter(|&i| saturation.contains(hsl[i].s) && lightness.contains(hsl[i].l))
+            .map(|i| {
+                let score = SATURATION_WEIGHT * (1.0 - (hsl[i].s - saturation.target).abs())
+                    + LIGHTNESS_WEIGHT * (1.0 - (hsl[i].l - lightness.target).abs())
+                    + DOMINANCE_WEIGHT * (palette[i].1 / max_dominance);
+                (i, score)
+            })
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).expect("NaN encountered"));
+
+        if let Some((i, _)) = best {
+            roles[i] = Some(*role);
+        }
+    }
+
+    return roles;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::RGB;
+
+    fn lab(r: u8, g: u8, b: u8) -> LAB {
+        return LAB::from(&RGB { r, g, b });
+    }
+
+    #[test]
+    fn assigns_each_role_once() {
+        let palette = vec![
+            (lab(250, 150, 150), 0.1),  // Light Vibrant
+            (lab(230, 30, 30), 0.3),    // Vibrant
+            (lab(110, 10, 10), 0.1),    // Dark Vibrant
+            (lab(200, 190, 180), 0.1),  // Light Muted
+            (lab(140, 120, 110), 0.2),  // Muted
+            (lab(60, 55, 50), 0.2),     // Dark Muted
+        ];
+        let roles = assign_roles(&palette);
+        assert!(roles.iter().zip(ROLES.iter()).all(|(role, expected)| *role == Some(*expected)));
+    }
+
+    #[test]
+    fn prefers_dominant_colors_for_a_role() {
+        let palette = vec![(lab(225, 35, 35), 0.1), (lab(230, 30, 30), 0.9)];
+        let roles = assign_roles(&palette);
+        assert!(roles[1] == Some(Role::Vibrant));
+        assert!(roles[0] != Some(Role::Vibrant));
+    }
+
+    #[test]
+    fn leaves_colors_without_roles() {
+        // Pure gray is not saturated enough for the vibrant roles, and only the muted roles remain
+        let roles = assign_roles(&[(lab(128, 128, 128), 1.0)]);
+        assert!(roles[0] == Some(Role::Muted));
+
+        let roles = assign_roles(&[(lab(128, 128, 128), 0.5), (lab(130, 130, 130), 0.5), (lab(126, 126, 126), 0.5)]);
+        assert_eq!(roles.iter().filter(|role| role.is_some()).count(), 1);
+        assert!(assign_roles(&[]).is_empty());
+    }
+}
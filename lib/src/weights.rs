@@ -1,13 +1,73 @@
-use crate::color;
+use crate::{color, filters::skin_tone};
 
+use std::str::FromStr;
 use wasm_bindgen::{prelude::*};
 use color::{LAB};
 
+/// Weight of a color used while recalculating the cluster means
+///
+/// It is implemented for every closure of the form `Fn(&LAB) -> f32`, so weights
+/// can capture parameters, and for boxed weights. Weights must be `Send + Sync` to be shared across threads.
+pub trait Weight: Send + Sync {
+    fn weight(&self, color: &LAB) -> f32;
+}
+
+impl<F> Weight for F where F: Fn(&LAB) -> f32 + Send + Sync {
+    fn weight(&self, color: &LAB) -> f32 {
+        self(color)
+    }
+}
+
+impl Weight for Box<dyn Weight> {
+    fn weight(&self, color: &LAB) -> f32 {
+        self.as_ref().weight(color)
+    }
+}
+
+impl<'a> Weight for &'a dyn Weight {
+    fn weight(&self, color: &LAB) -> f32 {
+        (*self).weight(color)
+    }
+}
+
+/// Weight stored on the heap, as returned by `resolve_mood` and `parse_blend`
+pub type BoxedWeight = Box<dyn Weight>;
+
+/// Plain weight function, which implements `Weight` like any other function
 pub type WeightFn = fn(&LAB) -> f32;
 
 #[wasm_bindgen]
 pub enum Mood {
     Dominant,
+    Vibrant,
+    Muted,
+    Light,
+    Dark,
+    Skin,
+    NoSkin,
+}
+
+impl FromStr for Mood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "dominant" => Ok(Mood::Dominant),
+            "vibrant" => Ok(Mood::Vibrant),
+            "muted" => Ok(Mood::Muted),
+            "light" => Ok(Mood::Light),
+            "dark" => Ok(Mood::Dark),
+            "skin" => Ok(Mood::Skin),
+            "no-skin" => Ok(Mood::NoSkin),
+            _ => Err(format!("Unknown mood '{}'", s)),
+        }
+    }
+}
+
+// Quadratically increasing weight for a value from 0 to 100, normalized to stay within 0.04 - 1.0
+// so that moods have comparable weights while blending
+fn boost(val: f32) -> f32 {
+    ((1.0 + val.max(0.0) / 25.0) / 5.0).powi(2)
 }
 
 // Weight function to calculate dominant colors
@@ -16,9 +76,255 @@ fn dominant(_: &LAB) -> f32 {
     1.0
 }
 
+// Weight function to calculate vibrant colors
+// Colors with higher chroma are given more weight
+fn vibrant(color: &LAB) -> f32 {
+    boost(color.chroma())
+}
+
+// Weight function to calculate muted colors
+// Colors with lower chroma are given more weight
+fn muted(color: &LAB) -> f32 {
+    0.04 / boost(color.chroma())
+}
+
+// Weight function to calculate light colors
+// Colors with higher lightness are given more weight
+fn light(color: &LAB) -> f32 {
+    boost(color.l)
+}
+
+// Weight function to calculate dark colors
+// Colors with lower lightness are given more weight
+fn dark(color: &LAB) -> f32 {
+    boost(100.0 - color.l)
+}
+
+// Weight function to focus on skin tones
+// Colors which look like skin tones are given more weight
+fn skin(color: &LAB) -> f32 {
+    0.04 + 0.96 * skin_tone(color)
+}
+
+// Weight function to suppress skin tones
+// Colors which look like skin tones are given less weight
+fn no_skin(color: &LAB) -> f32 {
+    1.0 - 0.96 * skin_tone(color)
+}
+
+/// Smallest spread of `TargetHue` in degrees, to keep the weights from becoming NaN
+const MIN_SPREAD: f32 = 0.01;
+
+/// Weight which prefers colors close to a target hue, as an example of a weight with parameters
+///
+/// Weight falls off as a gaussian of the hue difference (in degrees) with `spread` as its
+/// standard deviation. Colors without chroma get a weight close to zero.
+pub struct TargetHue {
+    hue: f32,
+    spread: f32,
+}
+
+impl TargetHue {
+    /**
+     * Creates the weight for a hue in degrees, where spreads below 0.01 degrees are raised to it
+     */
+    pub fn new(hue: f32, spread: f32) -> Self {
+        return TargetHue { hue, spread: spread.abs().max(MIN_SPREAD) };
+    }
+}
+
+impl Weight for TargetHue {
+    fn weight(&self, color: &LAB) -> f32 {
+        let hue = color.b.atan2(color.a).to_degrees();
+        let mut diff = (hue - self.hue).abs() % 360.0;
+        if diff > 180.0 {
+            diff = 360.0 - diff;
+        }
+
+        let closeness = (-0.5 * (diff / self.spread).powi(2)).exp();
+        // Scale by chroma so that grays do not take over the means
+        return closeness * (color.chroma() / 100.0).min(1.0) + f32::EPSILON;
+    }
+}
+
+/// Ways in which weights can be combined in a `Blend`
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub enum BlendMode {
+    /// Weighted sum of the weights using the factors
+    Linear,
+    /// Product of the weights raised to the power of the factors
+    Multiplicative,
+}
+
+impl FromStr for BlendMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "linear" => Ok(BlendMode::Linear),
+            "multiplicative" => Ok(BlendMode::Multiplicative),
+            _ => Err(format!("Unknown blend mode '{}'", s)),
+        }
+    }
+}
+
+/// Weight which combines several weights, each with a factor
+///
+/// A linear blend whose factors do not add up to more than 0, like an empty blend, weights every color 1.0.
+///
+/// # Example
+/// ```
+/// # use pigmnts::weights::{Blend, BlendMode, Mood, resolve_mood};
+/// let blend = Blend::new(BlendMode::Linear)
+///     .with(resolve_mood(&Mood::Dominant), 0.7)
+///     .with(resolve_mood(&Mood::Vibrant), 0.3);
+/// ```
+pub struct Blend {
+    pub mode: BlendMode,
+    pub weights: Vec<(BoxedWeight, f32)>,
+}
+
+impl Blend {
+    pub fn new(mode: BlendMode) -> Self {
+        Blend {
+            mode,
+            weights: Vec::new(),
+        }
+    }
+
+    /**
+     * Adds a weight with its factor to the blend
+     */
+    pub fn with(mut self, weight: BoxedWeight, factor: f32) -> Self {
+        self.weights.push((weight, factor));
+        self
+    }
+}
+
+impl Weight for Blend {
+    fn weight(&self, color: &LAB) -> f32 {
+        match self.mode {
+            BlendMode::Linear => {
+                let total: f32 = self.weights.iter().map(|(_, factor)| factor).sum();
+                if total <= 0.0 {
+                    return 1.0;
+                }
+                self.weights
+                    .iter()
+                    .map(|(weight, factor)| factor * weight.weight(color))
+                    .sum::<f32>() / total
+            },
+            BlendMode::Multiplicative => {
+                self.weights
+                    .iter()
+                    .map(|(weight, factor)| weight.weight(color).powf(*factor))
+                    .product()
+            },
+        }
+    }
+}
+
+/// Parses a blend of moods of the form `vibrant:0.3,dominant:0.7`
+///
+/// A mood without a factor has a factor of 1. A single mood with a factor of 1 resolves to its own
+/// weight function, and at least one mood must have a non-zero factor.
+pub fn parse_blend(moods: &str, mode: BlendMode) -> Result<BoxedWeight, String> {
+    let mut blend = Blend::new(mode);
+
+    for item in moods.split(',') {
+        let mut parts = item.splitn(2, ':');
+        let mood: Mood = parts.next().unwrap().parse()?;
+        let factor = match parts.next() {
+            Some(f) => match f.trim().parse::<f32>() {
+                Ok(f) if f >= 0.0 => f,
+                _ => return Err(format!("Invalid factor for mood '{}'", item)),
+            },
+            None => 1.0,
+        };
+
+        blend = blend.with(resolve_mood(&mood), factor);
+    }
+
+    if blend.weights.iter().all(|(_, factor)| *factor == 0.0) {
+        return Err(String::from("At least one mood should have a non-zero factor"));
+    }
+    if blend.weights.len() == 1 && blend.weights[0].1 == 1.0 {
+        return Ok(blend.weights.pop().unwrap().0);
+    }
+
+    return Ok(Box::new(blend));
+}
+
 // Resolve the mood to return appropriate weight function
-pub fn resolve_mood(mood: &Mood) -> WeightFn {
+pub fn resolve_mood(mood: &Mood) -> BoxedWeight {
     match mood {
-        Mood::Dominant => dominant,
+        Mood::Dominant => Box::new(dominant),
+        Mood::Vibrant => Box::new(vibrant),
+        Mood::Muted => Box::new(muted),
+        Mood::Light => Box::new(light),
+        Mood::Dark => Box::new(dark),
+        Mood::Skin => Box::new(skin),
+        Mood::NoSkin => Box::new(no_skin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_hue_without_spread_is_not_nan() {
+        let weight = TargetHue::new(40.0, 0.0);
+        let red = LAB { l: 54.0, a: 80.0, b: 67.0 };
+        let gray = LAB { l: 50.0, a: 0.0, b: 0.0 };
+
+        assert!(!weight.weight(&red).is_nan());
+        assert!(!weight.weight(&gray).is_nan());
+        assert!(TargetHue::new(40.0, 30.0).weight(&red) > TargetHue::new(220.0, 30.0).weight(&red));
+    }
+
+    #[test]
+    fn blend_without_factors_is_neutral() {
+        let color = LAB { l: 54.0, a: 80.0, b: 67.0 };
+        assert_eq!(Blend::new(BlendMode::Linear).weight(&color), 1.0);
+        assert_eq!(Blend::new(BlendMode::Multiplicative).weight(&color), 1.0);
+
+        let zero = Blend::new(BlendMode::Linear).with(resolve_mood(&Mood::Vibrant), 0.0);
+        assert_eq!(zero.weight(&color), 1.0);
+
+        let half = Blend::new(BlendMode::Linear)
+            .with(Box::new(|_: &LAB| 0.2), 1.0)
+            .with(Box::new(|_: &LAB| 0.6), 1.0);
+        assert!((half.weight(&color) - 0.4).abs() < 1e-6);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parses_blends() {
+        let color = LAB { l: 54.0, a: 80.0, b: 67.0 };
+
+        let single = parse_blend("vibrant", BlendMode::Linear).unwrap();
+        assert_eq!(single.weight(&color), vibrant(&color));
+
+        let mixed = parse_blend("vibrant:0.3, dark:0.7", BlendMode::Linear).unwrap();
+        assert!((mixed.weight(&color) - (0.3 * vibrant(&color) + 0.7 * dark(&color))).abs() < 1e-6);
+
+        // Factor of a single mood is kept in the multiplicative mode
+        let power = parse_blend("vibrant:0.5", BlendMode::Multiplicative).unwrap();
+        assert!((power.weight(&color) - vibrant(&color).powf(0.5)).abs() < 1e-6);
+    }
+
+    #[test]
+    fn rejects_invalid_blends() {
+        for moods in ["vibrant:0", "vibrant:0,dark:0", "vibrant:-1", "vibrant:high", "vibrant,sunny", ""].iter() {
+            assert!(parse_blend(moods, BlendMode::Linear).is_err(), "{} is accepted", moods);
+        }
+    }
+
+    #[test]
+    fn functions_are_weights() {
+        let function: WeightFn = |color: &LAB| color.l;
+        let boxed: BoxedWeight = Box::new(function);
+        assert_eq!(boxed.weight(&LAB { l: 30.0, a: 0.0, b: 0.0 }), 30.0);
+    }
+}
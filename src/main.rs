@@ -1,12 +1,22 @@
 pub mod utils;
 
-use clap::{App, Arg, values_t};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand, value_t, values_t};
 use spinners::{Spinner, Spinners};
 use termion::{color, style};
 use prettytable::{Table, format, Row, cell, row};
-use std::{time::Instant, process};
-use image::GenericImageView;
-use pigmnts::{Pixels, color::{LAB, RGB, HSL}, weights, pigments_pixels};
+use std::{time::Instant, process, fs};
+use image::{DynamicImage, ImageFormat, imageops::{self, FilterType}, codecs::hdr::HdrDecoder};
+use pigmnts::{
+    Pixels, color::{LAB, RGB, RGBA, Contrast, Deficiency, HSL, HSV, HWB, LCH, CMYK, OKLAB, OKLCH, RGBSpace, WhitePoint}, weights, pigments_pixels_in,
+    grid_from_rgba, grid_from_rgba16, grid_from_rgba32f, colors_from_rgba, palette_alpha, ClusterSpace,
+    format::{self as css, Format, Syntax, ToCSS, color_function},
+    background::detect_background,
+    gamut::in_gamut,
+    filters::{Exclude, exclude_pixels},
+    palette::{Palette, SortOrder},
+    accessibility::WCAGLevel,
+    harmony::{Harmony, HueSpace},
+};
 
 /// Creates a vector of strings with elements added conditonally
 ///
@@ -31,39 +41,332 @@ macro_rules! conditional_vec {
     };
 }
 
-/// Creates a color palette from image
+/// Options used while creating the palettes
+struct Options {
+    /// Color over which transparent pixels are composited
+    background: RGB,
+    /// Tolerance used for detecting the background, `None` when detection is disabled
+    bg_tolerance: Option<f32>,
+    /// Colors excluded from the pixels before clustering
+    excludes: Vec<Exclude>,
+    /// Minimum dominance (0 - 1) of colors in the palette
+    min_dominance: f32,
+    /// Weight function resolved from the blend of moods
+    weight: weights::BoxedWeight,
+    /// Color space in which the pixels are clustered
+    space: ClusterSpace,
+    /// Whether the alpha of the palette colors is calculated
+    alpha: bool,
+    /// Order of the colors in the palette
+    sort: SortOrder,
+}
+
+/// Decoded image which is resized for creating the palette
+enum Decoded {
+    /// Image with 8-bit or 16-bit channels
+    Standard(image::DynamicImage),
+    /// HDR image with linear floating point channels
+    HighDynamicRange(image::ImageBuffer<image::Rgb<f32>, Vec<f32>>),
+}
+
+/// Loads an image from a file or an HTTP(S) URL and resizes it to fit within 512x512
 ///
-/// Image is loaded from `image_path` and a palette of `count` colors are created
-fn pigmnts(image_path: &str, count: u8) -> Result<(Vec<(LAB, f32)>, u128), Box<dyn std::error::Error>> {
-    let mut img;
+/// HDR images are decoded with linear floating point channels instead of being converted to 8-bit
+fn load_image(image_path: &str) -> Result<Decoded, Box<dyn std::error::Error>> {
+    let mut buf: Vec<u8> = vec![];
 
     if image_path.starts_with("http://") || image_path.starts_with("https://") {
         let mut res = reqwest::blocking::get(image_path)?;
-        let mut buf: Vec<u8> = vec![];
         res.copy_to(&mut buf)?;
-        img = image::load_from_memory(buf.as_slice())?;
     }
     else {
-        img = image::open(image_path)?;
+        buf = fs::read(image_path)?;
     }
 
-    img = img.resize(512, 512, image::imageops::FilterType::CatmullRom);
+    let format = image::guess_format(&buf).or_else(|_| ImageFormat::from_path(image_path))?;
+    if format != ImageFormat::Hdr {
+        let img = image::load_from_memory_with_format(&buf, format)?;
+        return Ok(Decoded::Standard(resize_image(img)));
+    }
+
+    let decoder = HdrDecoder::new(buf.as_slice())?;
+    let (width, height) = (decoder.metadata().width, decoder.metadata().height);
+    let data: Vec<f32> = decoder
+        .read_image_hdr()?
+        .iter()
+        .flat_map(|pix| pix.0.to_vec())
+        .collect();
+    let img = image::ImageBuffer::from_raw(width, height, data).ok_or("Invalid HDR image data")?;
+
+    let (w, h) = fit_size(width, height);
+    return Ok(Decoded::HighDynamicRange(imageops::resize(&img, w, h, FilterType::CatmullRom)));
+}
+
+/// Size of the image resized to fit within 512x512 while preserving the aspect ratio like `DynamicImage::resize`
+fn fit_size(width: u32, height: u32) -> (u32, u32) {
+    let ratio = (512.0 / width as f64).min(512.0 / height as f64);
+    return ((width as f64 * ratio).round().max(1.0) as u32, (height as f64 * ratio).round().max(1.0) as u32);
+}
+
+/// Resizes the image to fit within 512x512
+///
+/// Images with an alpha channel are resized with premultiplied alpha at 16 bits, so that the color
+/// of transparent pixels does not bleed into the edges of visible pixels
+fn resize_image(img: DynamicImage) -> DynamicImage {
+    if !img.color().has_alpha() {
+        return img.resize(512, 512, FilterType::CatmullRom);
+    }
+
+    let mut rgba = img.to_rgba16();
+    for pix in rgba.pixels_mut() {
+        let a = pix[3] as u32;
+        for c in 0..3 {
+            pix[c] = ((pix[c] as u32 * a + 32767) / 65535) as u16;
+        }
+    }
+
+    let (width, height) = fit_size(rgba.width(), rgba.height());
+    let mut resized = imageops::resize(&rgba, width, height, FilterType::CatmullRom);
+
+    // Filters can overshoot, so colors are clamped to the alpha before unpremultiplying
+    for pix in resized.pixels_mut() {
+        let a = pix[3] as u32;
+        for c in 0..3 {
+            pix[c] = if a == 0 { 0 } else { ((pix[c] as u32).min(a) * 65535 + a / 2) / a } as u16;
+        }
+    }
+
+    return DynamicImage::ImageRgba16(resized);
+}
+
+/// Creates a color palette from image
+///
+/// Image is loaded from `image_path` and a palette of `count` colors are created.
+/// When background detection is enabled, the detected background is excluded from the palette
+/// and is returned along with its coverage. Palette colors are also returned with their alpha,
+/// which are opaque unless alpha is enabled
+fn pigmnts(
+    image_path: &str,
+    count: u8,
+    options: &Options,
+) -> Result<(Palette, Vec<RGBA>, Option<(LAB, f32)>, u128), Box<dyn std::error::Error>> {
+    let decoded = load_image(image_path)?;
 
     // Start a timer
     let now = Instant::now();
 
-    let pixels: Pixels = img
-        .pixels()
-        .map(|(_, _, pix)| LAB::from_rgb(pix[0], pix[1], pix[2]))
-        .collect();
+    // 16-bit and HDR images are converted at full precision, 8-bit data is kept to calculate the alpha
+    let (grid, width, height, rgba8) = match decoded {
+        Decoded::HighDynamicRange(img) => {
+            let data: Vec<f32> = img.pixels().flat_map(|pix| vec![pix[0], pix[1], pix[2], 1.0]).collect();
+            (grid_from_rgba32f(&data, &options.background, true), img.width(), img.height(), None)
+        },
+        Decoded::Standard(img @ DynamicImage::ImageLuma16(_))
+        | Decoded::Standard(img @ DynamicImage::ImageLumaA16(_))
+        | Decoded::Standard(img @ DynamicImage::ImageRgb16(_))
+        | Decoded::Standard(img @ DynamicImage::ImageRgba16(_)) => {
+            let rgba = img.to_rgba16();
+            let grid = grid_from_rgba16(rgba.as_raw(), &options.background);
+            let rgba8 = if options.alpha { Some(img.to_rgba8().into_raw()) } else { None };
+            (grid, rgba.width(), rgba.height(), rgba8)
+        },
+        Decoded::Standard(img) => {
+            let rgba = img.to_rgba8();
+            let (width, height) = rgba.dimensions();
+            let data = rgba.into_raw();
+            (grid_from_rgba(&data, &options.background), width, height, Some(data))
+        },
+    };
 
-    let weightfn = weights::resolve_mood(&weights::Mood::Dominant);
-    let mut output = pigments_pixels(&pixels, count, weightfn, None);
+    let mut detected = None;
+    let pixels: Pixels = match options.bg_tolerance.and_then(|tolerance| {
+        detect_background(&grid, width as usize, height as usize, tolerance)
+    }) {
+        Some(bg) => {
+            detected = Some((bg.color.clone(), bg.coverage));
+            bg.exclude(&grid)
+        },
+        None => grid.into_iter().flatten().collect(),
+    };
+    let pixels = exclude_pixels(pixels, &options.excludes);
+
+    let mut palette = Palette::new(pigments_pixels_in(&pixels, count, options.weight.as_ref(), None, options.space))
+        .min_dominance(options.min_dominance);
+    palette.sort(options.sort);
 
-    // Sort the output colors based on dominance
-    output.sort_by(|(_, a), (_, b)| b.partial_cmp(a).unwrap());
+    let alphas = match rgba8 {
+        Some(data) if options.alpha => palette_alpha(&palette.colors(), &colors_from_rgba(&data), &options.background),
+        _ => palette.convert::<RGB>().iter().map(RGBA::from).collect(),
+    };
 
-    return Ok((output, now.elapsed().as_millis()));
+    return Ok((palette, alphas, detected, now.elapsed().as_millis()));
+}
+
+/// Formats whether the color is in the sRGB gamut, or was outside it and is mapped into it
+fn gamut_output(color: &LAB) -> &'static str {
+    return if in_gamut(color, RGBSpace::SRGB) { "in" } else { "mapped" };
+}
+
+/// Formats the LAB color (relative to D65) relative to the white point
+///
+/// CSS `lab()` is relative to D50, so other white points are shown as plain values
+fn lab_output(color: &LAB, white: WhitePoint, fmt: &Format) -> String {
+    if white == WhitePoint::D50 {
+        return color.to_css(fmt);
+    }
+    let lab = color.adapt(WhitePoint::D65, white);
+    return css::values(&[lab.l, lab.a, lab.b], fmt);
+}
+
+/// Formats the LCh of the LAB color (relative to D65) relative to the white point
+///
+/// CSS `lch()` is relative to D50, so other white points are shown as plain values
+fn lch_output(color: &LAB, white: WhitePoint, fmt: &Format) -> String {
+    if white == WhitePoint::D50 {
+        return LCH::from(color).to_css(fmt);
+    }
+    let lch = LCH::from(&color.adapt(WhitePoint::D65, white));
+    return css::values(&[lch.l, lch.c, lch.h], fmt);
+}
+
+/// Prints the contrast of every color of the palette as text (rows) over every color as background (columns)
+///
+/// Each value is shown in its text color over its background color
+fn print_contrast(colors: &[RGB], method: Contrast, fmt: &Format) {
+    let title = match method {
+        Contrast::WCAG => "WCAG 2.x contrast ratio",
+        Contrast::APCA => "APCA lightness contrast (Lc)",
+    };
+    println!("{}{}{}{}", color::Fg(color::White), style::Bold, title, style::Reset);
+
+    print!("{:9}", "");
+    for bg in colors.iter() {
+        print!("  {:^9}", bg.hex());
+    }
+    println!();
+
+    for text in colors.iter() {
+        print!("{:9}", text.hex());
+        for bg in colors.iter() {
+            print!(
+                "  {}{}{:^9}{}",
+                color::Fg(color::Rgb(text.r, text.g, text.b)),
+                color::Bg(color::Rgb(bg.r, bg.g, bg.b)),
+                fmt.number(method.calculate(text, bg)),
+                style::Reset
+            );
+        }
+        println!();
+    }
+    println!();
+}
+
+/// Prints the pairs of colors which become indistinguishable with each color vision deficiency
+///
+/// Each pair is shown with its original colors followed by the colors as seen with the deficiency
+fn print_confusions(palette: &Palette, threshold: f32, fmt: &Format) {
+    println!(
+        "{}{}Color vision deficiencies{} (Delta E below {})",
+        color::Fg(color::White),
+        style::Bold,
+        style::Reset,
+        fmt.number(threshold)
+    );
+
+    let colors = palette.convert::<RGB>();
+    let preview = |rgb: &RGB| format!("{}  {}", color::Bg(color::Rgb(rgb.r, rgb.g, rgb.b)), style::Reset);
+    for deficiency in Deficiency::ALL.iter() {
+        let confusions = palette.confusions(*deficiency, threshold);
+        if confusions.is_empty() {
+            println!("  {:14}{}✓{} No indistinguishable colors", deficiency.to_string(), color::Fg(color::Green), style::Reset);
+            continue;
+        }
+
+        for (i, confusion) in confusions.iter().enumerate() {
+            let (a, b) = (&colors[confusion.swatches.0], &colors[confusion.swatches.1]);
+            println!(
+                "  {:14}{}{} {} {}  →  {}{}  Delta E {}",
+                if i == 0 { deficiency.to_string() } else { String::new() },
+                preview(a),
+                preview(b),
+                a.hex(),
+                b.hex(),
+                preview(&a.simulate(*deficiency)),
+                preview(&b.simulate(*deficiency)),
+                fmt.number(confusion.distance)
+            );
+        }
+    }
+    println!();
+}
+
+/// Generates color harmonies from the most dominant color of an image or from a given color
+fn harmonies(matches: &ArgMatches) {
+    let base = match matches.value_of("input") {
+        Some(image_path) => {
+            let options = Options {
+                background: RGB { r: 255, g: 255, b: 255 },
+                bg_tolerance: None,
+                excludes: Vec::new(),
+                min_dominance: 0.0,
+                weight: weights::parse_blend(matches.value_of("mood").unwrap(), weights::BlendMode::Linear).unwrap(),
+                space: ClusterSpace::CIELAB,
+                alpha: false,
+                sort: SortOrder::Dominance,
+            };
+            let (palette, _, _, _) = pigmnts(image_path, 5, &options).unwrap_or_else(|err| {
+                eprintln!("Problem creating palette: {}", err);
+                process::exit(1);
+            });
+
+            match palette.swatches.first() {
+                Some(swatch) => swatch.color.clone(),
+                None => {
+                    eprintln!("Problem creating palette: No colors found in {}", image_path);
+                    process::exit(1);
+                },
+            }
+        },
+        None => value_t!(matches, "color", LAB).unwrap(),
+    };
+
+    let space = value_t!(matches, "space", HueSpace).unwrap();
+    let types = values_t!(matches, "type", Harmony).unwrap_or(Harmony::ALL.to_vec());
+    let is_quiet = matches.is_present("quiet");
+
+    if !is_quiet {
+        let rgb = RGB::from(&base);
+        println!(
+            "{}{}Harmonies of{} {}  {} {}",
+            color::Fg(color::White),
+            style::Bold,
+            style::Reset,
+            color::Bg(color::Rgb(rgb.r, rgb.g, rgb.b)),
+            style::Reset,
+            rgb.hex()
+        );
+        println!();
+    }
+
+    for harmony in types.iter() {
+        let colors: Vec<RGB> = harmony.generate_in(&base, space).iter().map(RGB::from).collect();
+        let codes: Vec<String> = colors.iter().map(|rgb| rgb.hex()).collect();
+
+        if is_quiet {
+            // Quiet mode only shows the colors of each harmony separated by ':'
+            println!("{}", codes.join(":"));
+        } else {
+            let previews: Vec<String> = colors
+                .iter()
+                .map(|rgb| format!("{}    {}", color::Bg(color::Rgb(rgb.r, rgb.g, rgb.b)), style::Reset))
+                .collect();
+            println!("  {:21}{}  {}", harmony.to_string(), previews.join(""), codes.join(" "));
+        }
+    }
+
+    if !is_quiet {
+        println!();
+    }
 }
 
 fn main() {
@@ -71,6 +374,50 @@ fn main() {
         .version(env!("CARGO_PKG_VERSION"))
         .author(env!("CARGO_PKG_AUTHORS"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .subcommand(SubCommand::with_name("harmony")
+            .about("Generate color harmonies from the most dominant color of an image or from a color")
+            .arg(Arg::with_name("input")
+                .help("Image whose most dominant color is the base of the harmonies")
+                .value_name("FILE")
+                .required_unless("color")
+                .conflicts_with("color")
+                .index(1))
+            .arg(Arg::with_name("color")
+                .long("color")
+                .value_name("COLOR")
+                .help("Base color of the harmonies instead of an image")
+                .validator(|color| color.parse::<LAB>().map(|_| ()).map_err(|e| e.to_string()))
+                .takes_value(true))
+            .arg(Arg::with_name("type")
+                .short("t")
+                .long("type")
+                .value_name("HARMONIES")
+                .help("Comma separated harmonies to generate, all of them by default")
+                .possible_values(&["complementary", "split-complementary", "triadic", "tetradic", "analogous"])
+                .multiple(true)
+                .use_delimiter(true)
+                .takes_value(true))
+            .arg(Arg::with_name("space")
+                .long("space")
+                .value_name("SPACE")
+                .help("Color space in which the hue is rotated")
+                .possible_values(&["lch", "oklch"])
+                .default_value("oklch")
+                .takes_value(true))
+            .arg(Arg::with_name("mood")
+                .short("m")
+                .long("mood")
+                .value_name("MOODS")
+                .help("Mood of the palette from which the most dominant color of the image is picked")
+                .default_value("dominant")
+                .validator(|v| weights::parse_blend(&v, weights::BlendMode::Linear).map(|_| ()))
+                .takes_value(true))
+            .arg(Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .visible_alias("silent")
+                .help("Suppress the normal output")))
         .arg(Arg::with_name("count")
             .short("c")
             .long("count")
@@ -80,7 +427,7 @@ fn main() {
             .number_of_values(1)
             .takes_value(true))
         .arg(Arg::with_name("input")
-            .help("Sets the input file to use")
+            .help("Sets the input file to use, a file named harmony is given after -- or with a path like ./harmony")
             .value_name("FILE")
             .required(true)
             .multiple(true)
@@ -106,6 +453,32 @@ fn main() {
             .short("l")
             .long("lab")
             .help("Enable L*AB output of colors"))
+        .arg(Arg::with_name("lch")
+            .long("lch")
+            .help("Enable LCh output of colors"))
+        .arg(Arg::with_name("hsv")
+            .long("hsv")
+            .visible_alias("hsb")
+            .help("Enable HSV (or HSB) output of colors"))
+        .arg(Arg::with_name("hwb")
+            .long("hwb")
+            .help("Enable HWB output of colors"))
+        .arg(Arg::with_name("cmyk")
+            .long("cmyk")
+            .help("Enable CMYK output of colors"))
+        .arg(Arg::with_name("oklab")
+            .long("oklab")
+            .help("Enable OKLab output of colors"))
+        .arg(Arg::with_name("oklch")
+            .long("oklch")
+            .help("Enable OKLCH output of colors"))
+        .arg(Arg::with_name("p3")
+            .long("p3")
+            .help("Enable Display P3 output of colors as CSS color(display-p3 ...)"))
+        .arg(Arg::with_name("alpha")
+            .short("a")
+            .long("alpha")
+            .help("Include the alpha of colors in the Hex and RGB output"))
         .arg(Arg::with_name("dominance")
             .short("d")
             .long("dominance")
@@ -114,20 +487,190 @@ fn main() {
             .short("n")
             .long("name")
             .help("Enable nearest names of colors"))
+        .arg(Arg::with_name("role")
+            .long("role")
+            .help("Enable swatch roles of colors like Vibrant and Dark Muted"))
+        .arg(Arg::with_name("gamut")
+            .long("gamut")
+            .help("Enable whether colors are in the sRGB gamut or were outside it and mapped into it"))
+        .arg(Arg::with_name("background")
+            .short("b")
+            .long("background")
+            .value_name("COLOR")
+            .help("Background color over which transparent pixels are composited")
+            .default_value("#FFFFFF")
+            .validator(|color| color.parse::<RGB>().map(|_| ()).map_err(|e| e.to_string()))
+            .takes_value(true))
+        .arg(Arg::with_name("detect-bg")
+            .long("detect-bg")
+            .help("Detect the background of the image and exclude it from the palette"))
+        .arg(Arg::with_name("bg-tolerance")
+            .long("bg-tolerance")
+            .value_name("DELTA_E")
+            .help("Maximum color difference of background pixels when detecting the background")
+            .default_value("10")
+            .validator(|v| match v.parse::<f32>() {
+                Ok(t) if t >= 0.0 => Ok(()),
+                _ => Err(String::from("Tolerance must be a non-negative number")),
+            })
+            .takes_value(true))
+        .arg(Arg::with_name("exclude")
+            .short("e")
+            .long("exclude")
+            .value_name("COLORS")
+            .help("Comma separated colors to exclude: black[:L], white[:L], skin or <COLOR>[:DELTA_E]")
+            .multiple(true)
+            .number_of_values(1)
+            .validator(|v| utils::parse_excludes(&v).map(|_| ()))
+            .takes_value(true))
+        .arg(Arg::with_name("min-dominance")
+            .long("min-dominance")
+            .value_name("PERCENT")
+            .help("Hide colors with dominance below this percentage")
+            .default_value("0")
+            .validator(|v| match v.parse::<f32>() {
+                Ok(p) if p >= 0.0 && p <= 100.0 => Ok(()),
+                _ => Err(String::from("Minimum dominance must be a percentage between 0 and 100")),
+            })
+            .takes_value(true))
+        .arg(Arg::with_name("mood")
+            .short("m")
+            .long("mood")
+            .value_name("MOODS")
+            .help("Mood of the palette which decides the weights of colors: dominant, vibrant, muted, light, dark, skin, no-skin. \
+                Moods can be blended with factors like vibrant:0.3,dominant:0.7")
+            .default_value("dominant")
+            .validator(|v| weights::parse_blend(&v, weights::BlendMode::Linear).map(|_| ()))
+            .takes_value(true))
+        .arg(Arg::with_name("blend")
+            .long("blend")
+            .value_name("MODE")
+            .help("Blend mode used to combine the moods")
+            .possible_values(&["linear", "multiplicative"])
+            .default_value("linear")
+            .takes_value(true))
+        .arg(Arg::with_name("sort")
+            .long("sort")
+            .value_name("ORDER")
+            .help("Order of the colors in the palette, path orders them for smooth gradients")
+            .possible_values(&["dominance", "hue", "lightness", "chroma", "path"])
+            .default_value("dominance")
+            .takes_value(true))
+        .arg(Arg::with_name("space")
+            .long("space")
+            .value_name("SPACE")
+            .help("Color space in which the pixels are clustered")
+            .possible_values(&["cielab", "oklab"])
+            .default_value("cielab")
+            .takes_value(true))
+        .arg(Arg::with_name("white")
+            .short("w")
+            .long("white")
+            .value_name("POINT")
+            .help("Reference white point of the L*AB and LCh output, d50 is used by CSS while d65 is shown as plain values")
+            .possible_values(&["d50", "d65"])
+            .default_value("d50")
+            .takes_value(true))
+        .arg(Arg::with_name("precision")
+            .long("precision")
+            .value_name("DIGITS")
+            .help("Maximum number of decimal places in the output of colors")
+            .default_value("2")
+            .validator(|v| v.parse::<usize>().map(|_| ()).map_err(|_| String::from("Precision must be a non-negative integer")))
+            .takes_value(true))
+        .arg(Arg::with_name("contrast")
+            .long("contrast")
+            .value_name("METHOD")
+            .help("Show the contrast of the colors as text over each other")
+            .possible_values(&["wcag", "apca"])
+            .takes_value(true))
+        .arg(Arg::with_name("check-cvd")
+            .long("check-cvd")
+            .help("Show the pairs of colors which become indistinguishable with color vision deficiencies"))
+        .arg(Arg::with_name("cvd-threshold")
+            .long("cvd-threshold")
+            .value_name("DELTA_E")
+            .help("Minimum color difference of distinguishable colors when checking color vision deficiencies")
+            .default_value("10")
+            .validator(|v| match v.parse::<f32>() {
+                Ok(t) if t >= 0.0 => Ok(()),
+                _ => Err(String::from("Threshold must be a non-negative number")),
+            })
+            .takes_value(true))
+        .arg(Arg::with_name("text-level")
+            .long("text-level")
+            .value_name("LEVEL")
+            .help("WCAG level met by the text shown over the colors in the preview")
+            .possible_values(&["aa-large", "aa", "aaa"])
+            .default_value("aa")
+            .takes_value(true))
+        .arg(Arg::with_name("css")
+            .long("css")
+            .value_name("SYNTAX")
+            .help("Syntax of the CSS color functions: legacy (comma separated) or modern (space separated)")
+            .possible_values(&["legacy", "modern"])
+            .default_value("legacy")
+            .takes_value(true))
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("harmony") {
+        harmonies(matches);
+        return;
+    }
+
     let image_paths = matches.values_of("input").unwrap();
     let mut counts = values_t!(matches, "count", u8).unwrap_or(Vec::new());
     let is_quiet = matches.is_present("quiet");
     let is_rgb = matches.is_present("rgb");
     let is_hsl = matches.is_present("hsl");
     let is_lab = matches.is_present("lab");
+    let is_lch = matches.is_present("lch");
+    let is_hsv = matches.is_present("hsv");
+    let is_hwb = matches.is_present("hwb");
+    let is_cmyk = matches.is_present("cmyk");
+    let is_oklab = matches.is_present("oklab");
+    let is_oklch = matches.is_present("oklch");
+    let is_p3 = matches.is_present("p3");
     let is_dom = matches.is_present("dominance");
     let is_name = matches.is_present("name");
+    let is_role = matches.is_present("role");
+    let is_gamut = matches.is_present("gamut");
     let mut is_hex = matches.is_present("hex");
+    let white = value_t!(matches, "white", WhitePoint).unwrap();
+    let contrast = value_t!(matches, "contrast", Contrast).ok();
+    let text_level = value_t!(matches, "text-level", WCAGLevel).unwrap();
+    let cvd_threshold = if matches.is_present("check-cvd") {
+        Some(value_t!(matches, "cvd-threshold", f32).unwrap())
+    } else {
+        None
+    };
+    let fmt = Format {
+        precision: value_t!(matches, "precision", usize).unwrap(),
+        syntax: value_t!(matches, "css", Syntax).unwrap(),
+    };
+    let options = Options {
+        background: value_t!(matches, "background", RGB).unwrap(),
+        bg_tolerance: if matches.is_present("detect-bg") {
+            Some(value_t!(matches, "bg-tolerance", f32).unwrap())
+        } else {
+            None
+        },
+        excludes: matches
+            .values_of("exclude")
+            .map(|values| values.flat_map(|v| utils::parse_excludes(v).unwrap()).collect())
+            .unwrap_or(Vec::new()),
+        min_dominance: value_t!(matches, "min-dominance", f32).unwrap() / 100.0,
+        weight: weights::parse_blend(
+            matches.value_of("mood").unwrap(),
+            value_t!(matches, "blend", weights::BlendMode).unwrap(),
+        ).unwrap(),
+        space: value_t!(matches, "space", ClusterSpace).unwrap(),
+        alpha: matches.is_present("alpha"),
+        sort: value_t!(matches, "sort", SortOrder).unwrap(),
+    };
 
     // Hex format is enabled when other formats are disabled
-    if !is_rgb && !is_hsl & !is_lab {
+    if !is_rgb && !is_hsl && !is_lab && !is_lch && !is_hsv && !is_hwb && !is_cmyk && !is_oklab && !is_oklch && !is_p3 {
         is_hex = true;
     }
 
@@ -147,27 +690,71 @@ fn main() {
         if is_quiet {
             // Quiet mode only shows the result separated by ':'
 
-            let (result, _) = pigmnts(image_path, counts[i])
+            let (palette, alphas, detected, _) = pigmnts(image_path, counts[i], &options)
                 .unwrap_or_else(|err| {
                     eprintln!("Problem creating palette: {}", err);
                     process::exit(1);
                 });
 
-            for (color, dominance) in result.iter() {
+            for (swatch, rgba) in palette.iter().zip(alphas.iter()) {
+                let color = &swatch.color;
                 let rgb = RGB::from(color);
 
                 let record = conditional_vec![
-                    is_hex => || rgb.hex(),
-                    is_rgb => || rgb,
-                    is_hsl => || HSL::from(color),
-                    is_lab => || color,
-                    is_dom => || dominance * 100.0,
-                    is_name => || utils::near_color_name(color)
+                    is_hex => || if options.alpha { rgba.hex() } else { rgb.hex() },
+                    is_rgb => || if options.alpha { rgba.to_css(&fmt) } else { rgb.to_css(&fmt) },
+                    is_hsl => || HSL::from(color).to_css(&fmt),
+                    is_lab => || lab_output(color, white, &fmt),
+                    is_lch => || lch_output(color, white, &fmt),
+                    is_hsv => || css::hsv(&HSV::from(color), &fmt),
+                    is_hwb => || HWB::from(color).to_css(&fmt),
+                    is_cmyk => || CMYK::from(color).to_css(&fmt),
+                    is_oklab => || OKLAB::from(color).to_css(&fmt),
+                    is_oklch => || OKLCH::from(color).to_css(&fmt),
+                    is_p3 => || color_function(color, RGBSpace::DisplayP3, &fmt),
+                    is_dom => || fmt.number(swatch.dominance * 100.0),
+                    is_name => || utils::near_color_name(color),
+                    is_role => || swatch.role.map(|r| r.to_string()).unwrap_or_default(),
+                    is_gamut => || gamut_output(color)
                 ];
 
                 println!("{}", record.join(":"));
             }
 
+            // Contrast matrix is separated from the palette by an empty line
+            if let Some(method) = contrast {
+                let colors = palette.convert::<RGB>();
+                println!();
+                for text in colors.iter() {
+                    let row: Vec<String> = colors
+                        .iter()
+                        .map(|bg| fmt.number(method.calculate(text, bg)))
+                        .collect();
+                    println!("{}", row.join(":"));
+                }
+            }
+
+            // Indistinguishable pairs are separated from the palette by an empty line
+            if let Some(threshold) = cvd_threshold {
+                let colors = palette.convert::<RGB>();
+                println!();
+                for confusion in palette.check_deficiencies(threshold) {
+                    println!(
+                        "{}:{}:{}:{}",
+                        confusion.deficiency.to_string().to_lowercase(),
+                        colors[confusion.swatches.0].hex(),
+                        colors[confusion.swatches.1].hex(),
+                        fmt.number(confusion.distance)
+                    );
+                }
+            }
+
+            // Detected background is separated from the palette by an empty line
+            if let Some((bg, coverage)) = detected {
+                println!();
+                println!("background:{}:{}", RGB::from(&bg).hex(), fmt.number(coverage * 100.0));
+            }
+
         } else {
 
             print!("{}{}Creating a palette of ", color::Fg(color::White), style::Bold);
@@ -177,7 +764,7 @@ fn main() {
 
             // Show the spinner in the terminal
             let sp = Spinner::new(Spinners::Dots, String::default());
-            let (result, time) = pigmnts(image_path, counts[i])
+            let (palette, alphas, detected, time) = pigmnts(image_path, counts[i], &options)
                 .unwrap_or_else(|e| {
                     eprintln!(
                         "{}{}Problem creating palette:{} {}",
@@ -199,14 +786,26 @@ fn main() {
                     .padding(2, 2)
                     .build()
             );
+
+            // Gamut column is also shown when a color of the palette is outside the sRGB gamut
+            let is_mapped = is_gamut || palette.iter().any(|swatch| !in_gamut(&swatch.color, RGBSpace::SRGB));
             let titles = conditional_vec![
                 true => || "",  // Title for color preview
                 is_name => || "Name",
+                is_role => || "Role",
                 is_hex => || "Hex",
                 is_rgb => || "RGB",
                 is_hsl => || "HSL",
                 is_lab => || "LAB",
-                is_dom => || "Dominance"
+                is_lch => || "LCh",
+                is_hsv => || "HSV",
+                is_hwb => || "HWB",
+                is_cmyk => || "CMYK",
+                is_oklab => || "OKLab",
+                is_oklch => || "OKLCH",
+                is_p3 => || "Display P3",
+                is_dom => || "Dominance",
+                is_mapped => || "Gamut"
             ];
             table.set_titles(
                 Row::new(
@@ -217,20 +816,36 @@ fn main() {
                 )
             );
 
-            for (color, dominance) in result.iter() {
+            let texts = palette.text_colors(text_level);
+            for ((swatch, rgba), text) in palette.iter().zip(alphas.iter()).zip(texts.iter()) {
+                let color = &swatch.color;
                 let rgb = RGB::from(color);
                 let mut record = row![
-                    // Color preview is added
-                    format!("{}  {}", color::Bg(color::Rgb(rgb.r, rgb.g, rgb.b)), style::Reset)
+                    // Color preview is added with sample text in the suggested text color
+                    format!(
+                        "{}{} Aa {}",
+                        color::Bg(color::Rgb(rgb.r, rgb.g, rgb.b)),
+                        color::Fg(color::Rgb(text.color.r, text.color.g, text.color.b)),
+                        style::Reset
+                    )
                 ];
 
                 let values = conditional_vec![
                     is_name => || utils::near_color_name(color),
-                    is_hex => || rgb.hex(),
-                    is_rgb => || rgb,
-                    is_hsl => || HSL::from(color),
-                    is_lab => || color,
-                    is_dom => || format!("{}%", dominance * 100.0)
+                    is_role => || swatch.role.map(|r| r.to_string()).unwrap_or_default(),
+                    is_hex => || if options.alpha { rgba.hex() } else { rgb.hex() },
+                    is_rgb => || if options.alpha { rgba.to_css(&fmt) } else { rgb.to_css(&fmt) },
+                    is_hsl => || HSL::from(color).to_css(&fmt),
+                    is_lab => || lab_output(color, white, &fmt),
+                    is_lch => || lch_output(color, white, &fmt),
+                    is_hsv => || css::hsv(&HSV::from(color), &fmt),
+                    is_hwb => || HWB::from(color).to_css(&fmt),
+                    is_cmyk => || CMYK::from(color).to_css(&fmt),
+                    is_oklab => || OKLAB::from(color).to_css(&fmt),
+                    is_oklch => || OKLCH::from(color).to_css(&fmt),
+                    is_p3 => || color_function(color, RGBSpace::DisplayP3, &fmt),
+                    is_dom => || fmt.percent(swatch.dominance),
+                    is_mapped => || gamut_output(color)
                 ];
 
                 for value in values.iter() {
@@ -242,6 +857,29 @@ fn main() {
             table.printstd();
             println!();
 
+            if let Some(method) = contrast {
+                print_contrast(&palette.convert::<RGB>(), method, &fmt);
+            }
+
+            if let Some(threshold) = cvd_threshold {
+                print_confusions(&palette, threshold, &fmt);
+            }
+
+            if let Some((bg, coverage)) = detected {
+                let rgb = RGB::from(&bg);
+                println!(
+                    "{}{}Background:{} {}  {} {} ({:.2}% of the image)",
+                    color::Fg(color::White),
+                    style::Bold,
+                    style::Reset,
+                    color::Bg(color::Rgb(rgb.r, rgb.g, rgb.b)),
+                    style::Reset,
+                    rgb.hex(),
+                    coverage * 100.0
+                );
+                println!();
+            }
+
             println!(
                 "{}{}✓ Success!{} Took {}ms",
                 color::Fg(color::Green),
@@ -251,4 +889,34 @@ fn main() {
             );
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgba};
+
+    #[test]
+    fn resize_keeps_color_of_transparent_edges() {
+        // Red square and a half transparent blue strip over transparent black
+        let img = ImageBuffer::from_fn(80, 60, |x, y| {
+            if (10..50).contains(&x) && (10..50).contains(&y) {
+                Rgba([255u8, 0, 0, 255])
+            } else if (60..70).contains(&x) {
+                Rgba([0, 0, 255, 128])
+            } else {
+                Rgba([0, 0, 0, 0])
+            }
+        });
+
+        let resized = resize_image(DynamicImage::ImageRgba8(img)).to_rgba8();
+        assert_eq!(resized.dimensions(), (512, 384));
+        assert_eq!(resized.get_pixel(192, 192), &Rgba([255, 0, 0, 255]));
+
+        for pix in resized.pixels().filter(|pix| pix[3] > 0) {
+            // Edges fade out in alpha instead of darkening towards black
+            assert!(pix[1] <= 2, "{:?} is mixed with green", pix);
+            assert!(pix[0].max(pix[2]) >= 250, "{:?} is darkened", pix);
+        }
+    }
+}
@@ -1,19 +1,7 @@
-use pigmnts::color::{LAB, RGB};
+use pigmnts::{color::{LAB, RGB}, filters::Exclude};
 use std::collections::HashMap;
 use lazy_static::lazy_static;
 
-/// Coverts a hex string to RGB color
-fn hex_to_rgb(s: &str) -> RGB {
-  let hex = s.replace("#", "").to_lowercase();
-
-  let hex_num = usize::from_str_radix(&hex, 16).unwrap();
-  return RGB {
-      r: (hex_num >> 16) as u8,
-      g: ((hex_num >> 8) & 0x00FF) as u8,
-      b: (hex_num & 0x0000_00FF) as u8,
-  };
-}
-
 lazy_static! {
   static ref COLOR_NAMES: (Vec<String>, Vec<LAB>) = {
       let data: HashMap<String, String> = serde_cbor::from_slice(
@@ -23,7 +11,7 @@ lazy_static! {
 
       let values: Vec<LAB> = data
           .iter()
-          .map(|(val, _)| LAB::from(&hex_to_rgb(val)))
+          .map(|(val, _)| LAB::from(&RGB::from_hex(val).unwrap()))
           .collect();
 
       return (data.values().cloned().collect(), values);
@@ -33,4 +21,79 @@ lazy_static! {
 /// Returns nearest name of a color
 pub fn near_color_name(color: &LAB) -> &String {
   return &COLOR_NAMES.0[color.nearest(&COLOR_NAMES.1).0];
-}
\ No newline at end of file
+}
+
+/// Parses an exclusion of the form `black[:L]`, `white[:L]`, `skin` or `<COLOR>[:DELTA_E]`
+pub fn parse_exclude(value: &str) -> Result<Exclude, String> {
+  let mut parts = value.splitn(2, ':');
+  let name = parts.next().unwrap().trim().to_lowercase();
+  let param = match parts.next() {
+    Some(p) => Some(p.trim().parse::<f32>().map_err(|_| format!("Invalid number in exclusion '{}'", value))?),
+    None => None,
+  };
+
+  return match name.as_str() {
+    "black" => Ok(Exclude::Dark(param.unwrap_or(10.0))),
+    "white" => Ok(Exclude::Light(param.unwrap_or(95.0))),
+    "skin" if param.is_none() => Ok(Exclude::Skin),
+    color => match color.parse::<LAB>() {
+      Ok(lab) => Ok(Exclude::Color(lab, param.unwrap_or(10.0))),
+      Err(e) => Err(format!("Unknown color to exclude '{}': {}", value, e)),
+    },
+  };
+}
+
+/// Parses comma separated exclusions, where commas inside color functions like `rgb(255, 0, 0)` do not separate them
+pub fn parse_excludes(value: &str) -> Result<Vec<Exclude>, String> {
+  let mut excludes = Vec::new();
+  let mut depth = 0;
+  let mut start = 0;
+
+  for (i, c) in value.char_indices() {
+    match c {
+      '(' => depth += 1,
+      ')' => depth -= 1,
+      ',' if depth == 0 => {
+        excludes.push(parse_exclude(&value[start..i])?);
+        start = i + 1;
+      },
+      _ => {},
+    }
+  }
+  excludes.push(parse_exclude(&value[start..])?);
+
+  return Ok(excludes);
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn parses_excludes_with_color_functions() {
+    let excludes = parse_excludes("rgb(255, 0, 0):5,skin").unwrap();
+    assert_eq!(excludes.len(), 2);
+    match &excludes[0] {
+      Exclude::Color(color, tolerance) => {
+        assert!(color.distance(&LAB::from_rgb(255, 0, 0)) < 0.01);
+        assert_eq!(*tolerance, 5.0);
+      },
+      _ => panic!("rgb(255, 0, 0):5 is not parsed as a color"),
+    }
+    assert!(matches!(excludes[1], Exclude::Skin));
+
+    let excludes = parse_excludes("black, white:90,#00F,hsl(120, 100%, 50%)").unwrap();
+    assert!(matches!(excludes[0], Exclude::Dark(l) if l == 10.0));
+    assert!(matches!(excludes[1], Exclude::Light(l) if l == 90.0));
+    assert!(matches!(excludes[2], Exclude::Color(_, tolerance) if tolerance == 10.0));
+    assert!(matches!(excludes[3], Exclude::Color(_, _)));
+  }
+
+  #[test]
+  fn rejects_invalid_excludes() {
+    assert!(parse_excludes("skin:5").is_err());
+    assert!(parse_excludes("black:dark").is_err());
+    assert!(parse_excludes("notacolor").is_err());
+    assert!(parse_excludes("red,").is_err());
+  }
+}